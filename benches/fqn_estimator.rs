@@ -1,6 +1,6 @@
 use criterion::{Criterion, criterion_group, criterion_main};
 use fastrand::Rng;
-use fqn_estimator::QnScaleEstimator;
+use fqn_estimator::{QnScaleEstimator, SkipList};
 
 fn bench_1000_samples(criterion: &mut Criterion) {
     const LEN: usize = 1000;
@@ -9,7 +9,7 @@ fn bench_1000_samples(criterion: &mut Criterion) {
     let mut estimator = QnScaleEstimator::new(LEN);
     estimator.extend((0..LEN).map(|_| rng.i32(0..100)));
     criterion.bench_function("scale 1000", |bencher| {
-        bencher.iter(|| estimator.estimate())
+        bencher.iter(|| estimator.estimate());
     });
 }
 
@@ -20,7 +20,7 @@ fn bench_10_000_samples(criterion: &mut Criterion) {
     let mut estimator = QnScaleEstimator::new(LEN);
     estimator.extend((0..LEN).map(|_| rng.i32(0..1000)));
     criterion.bench_function("scale 10000", |bencher| {
-        bencher.iter(|| estimator.estimate())
+        bencher.iter(|| estimator.estimate());
     });
 }
 
@@ -31,7 +31,40 @@ fn bench_1_000_000_samples(criterion: &mut Criterion) {
     let mut estimator = QnScaleEstimator::new(LEN);
     estimator.extend((0..LEN).map(|_| rng.i32(0..10000)));
     criterion.bench_function("scale 1000000", |bencher| {
-        bencher.iter(|| estimator.estimate())
+        bencher.iter(|| estimator.estimate());
+    });
+}
+
+fn bench_1_000_000_samples_skip_list(criterion: &mut Criterion) {
+    const LEN: usize = 1_000_000;
+
+    let mut rng = Rng::with_seed(0);
+    let mut estimator = QnScaleEstimator::new(LEN).with_sorted_storage::<SkipList<_>>();
+    estimator.extend((0..LEN).map(|_| rng.i32(0..10000)));
+    criterion.bench_function("scale 1000000 (skip list)", |bencher| {
+        bencher.iter(|| estimator.estimate());
+    });
+}
+
+fn bench_push_1_000_000_samples(criterion: &mut Criterion) {
+    const LEN: usize = 1_000_000;
+
+    let mut rng = Rng::with_seed(0);
+    let mut estimator = QnScaleEstimator::new(LEN);
+    estimator.extend((0..LEN).map(|_| rng.i32(0..10000)));
+    criterion.bench_function("push 1000000", |bencher| {
+        bencher.iter(|| estimator.push(rng.i32(0..10000)));
+    });
+}
+
+fn bench_push_1_000_000_samples_skip_list(criterion: &mut Criterion) {
+    const LEN: usize = 1_000_000;
+
+    let mut rng = Rng::with_seed(0);
+    let mut estimator = QnScaleEstimator::new(LEN).with_sorted_storage::<SkipList<_>>();
+    estimator.extend((0..LEN).map(|_| rng.i32(0..10000)));
+    criterion.bench_function("push 1000000 (skip list)", |bencher| {
+        bencher.iter(|| estimator.push(rng.i32(0..10000)));
     });
 }
 
@@ -39,6 +72,9 @@ criterion_group!(
     benches,
     bench_1000_samples,
     bench_10_000_samples,
-    bench_1_000_000_samples
+    bench_1_000_000_samples,
+    bench_1_000_000_samples_skip_list,
+    bench_push_1_000_000_samples,
+    bench_push_1_000_000_samples_skip_list,
 );
 criterion_main!(benches);
@@ -0,0 +1,181 @@
+use std::f64::consts::PI;
+
+/// Beta distribution with the shape parameters `a` and `b`, which weighs the order statistics
+/// in the Harrell–Davis quantile estimator.
+#[derive(Copy, Clone, Debug)]
+pub struct Beta {
+    pub a: f64,
+    pub b: f64,
+}
+
+impl Beta {
+    /// Logarithm of the probability density function up to an additive constant.
+    fn ln_unnormalized_pdf(self, x: f64) -> f64 {
+        (self.a - 1.0).mul_add(x.ln(), (self.b - 1.0) * (-x).ln_1p())
+    }
+
+    /// Cumulative distribution function, that is the regularized incomplete beta function.
+    ///
+    /// This is the continued fraction from «Numerical Recipes», which converges
+    /// in `O(sqrt(max(a, b)))` iterations.
+    pub fn cdf(self, x: f64) -> f64 {
+        if x <= 0.0 {
+            return 0.0;
+        }
+        if x >= 1.0 {
+            return 1.0;
+        }
+        let Self { a, b } = self;
+        let ln_front = b.mul_add(
+            (-x).ln_1p(),
+            a.mul_add(x.ln(), ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b)),
+        );
+        if x < (a + 1.0) / (a + b + 2.0) {
+            ln_front.exp() * continued_fraction(x, a, b) / a
+        } else {
+            1.0 - ln_front.exp() * continued_fraction(1.0 - x, b, a) / b
+        }
+    }
+
+    /// Highest density interval of the specified width.
+    ///
+    /// This follows the trimmed Harrell–Davis paper: the interval sticks to a border
+    /// when the density is monotonic, and is found by bisection otherwise.
+    pub fn highest_density_interval(self, width: f64) -> (f64, f64) {
+        const EPSILON: f64 = 1e-9;
+
+        let Self { a, b } = self;
+        if width > 1.0 - EPSILON || (a < 1.0 + EPSILON && b < 1.0 + EPSILON) {
+            (0.0, 1.0)
+        } else if a < 1.0 + EPSILON {
+            (0.0, width)
+        } else if b < 1.0 + EPSILON {
+            (1.0 - width, 1.0)
+        } else {
+            // The left border has the same density as the right one:
+            let mode = (a - 1.0) / (a + b - 2.0);
+            let mut lower = (mode - width).max(0.0);
+            let mut upper = mode.min(1.0 - width);
+            for _ in 0..100 {
+                let middle = (lower + upper) / 2.0;
+                if self.ln_unnormalized_pdf(middle) < self.ln_unnormalized_pdf(middle + width) {
+                    lower = middle;
+                } else {
+                    upper = middle;
+                }
+            }
+            let left = (lower + upper) / 2.0;
+            (left, left + width)
+        }
+    }
+}
+
+/// Evaluate the continued fraction of the incomplete beta function with the modified Lentz's method.
+#[allow(clippy::many_single_char_names)]
+fn continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const MAX_ITERATIONS: u32 = 100_000;
+    const EPSILON: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = f64::from(m);
+        let m2 = 2.0 * m;
+
+        // Even step:
+        let numerator = m * (b - m) * x / ((a - 1.0 + m2) * (a + m2));
+        d = 1.0 / clamp(numerator.mul_add(d, 1.0));
+        c = clamp(1.0 + numerator / c);
+        fraction *= d * c;
+
+        // Odd step:
+        let numerator = -(a + m) * (a + b + m) * x / ((a + m2) * (a + 1.0 + m2));
+        d = 1.0 / clamp(numerator.mul_add(d, 1.0));
+        c = clamp(1.0 + numerator / c);
+        let delta = d * c;
+        fraction *= delta;
+
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    fraction
+}
+
+/// Logarithm of the gamma function, using the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula:
+        return (PI / (PI * x).sin().abs()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .zip(1..)
+        .fold(COEFFICIENTS[0], |sum, (coefficient, i)| {
+            sum + coefficient / (x + f64::from(i))
+        });
+    let t = x + G + 0.5;
+    (x + 0.5).mul_add(t.ln(), (2.0 * PI).sqrt().ln()) - t + sum.ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ln_gamma_ok() {
+        for (x, expected) in [
+            (0.5, PI.sqrt().ln()),
+            (1.0, 0.0),
+            (5.0, 24.0_f64.ln()),
+            (0.1, 2.252_712_651_734_206),
+        ] {
+            let value = ln_gamma(x);
+            assert!((value - expected).abs() < 1e-12, "x: {x}, value: {value}");
+        }
+    }
+
+    #[test]
+    fn cdf_ok() {
+        // Uniform distribution:
+        assert!((Beta { a: 1.0, b: 1.0 }.cdf(0.3) - 0.3).abs() < 1e-12);
+
+        // `I_x(a, 1) = x^a`:
+        assert!((Beta { a: 2.5, b: 1.0 }.cdf(0.7) - 0.7_f64.powf(2.5)).abs() < 1e-12);
+
+        // Symmetry:
+        let beta = Beta { a: 500.5, b: 500.5 };
+        assert!((beta.cdf(0.5) - 0.5).abs() < 1e-12);
+        assert!((beta.cdf(0.49) + beta.cdf(0.51) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn highest_density_interval_ok() {
+        let (left, right) = Beta { a: 3.0, b: 3.0 }.highest_density_interval(0.2);
+        assert!((left - 0.4).abs() < 1e-9, "left: {left}");
+        assert!((right - 0.6).abs() < 1e-9, "right: {right}");
+
+        assert_eq!(
+            Beta { a: 0.5, b: 3.0 }.highest_density_interval(0.2),
+            (0.0, 0.2)
+        );
+    }
+}
@@ -41,14 +41,9 @@ impl<V, I: ExactSizeIterator<Item = V>> ExactSizeIterator for DashIter<V, I> {
         let count = self.inner.len();
         assert_ne!(count, 0, "the inner length should not be zero");
 
-        // First element is always included:
-        1 + (
-            // Count subsequent elements every `step`:
-            count - 1
-            // Round up to the closest integer to account for the possible last element:
-            + self.step
-                - 1
-        ) / self.step
+        // First element is always included, then count subsequent elements every `step`,
+        // rounding up to account for the possible last element:
+        1 + (count - 1).div_ceil(self.step)
     }
 }
 
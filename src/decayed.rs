@@ -0,0 +1,184 @@
+use std::{fmt::Debug, ops::Sub};
+
+use crate::{
+    ChronologicalStorage,
+    scale::{ScaleEstimate, ScaleKind},
+    select::select_weighted_kth_statistic,
+    sorted_vec::{SortedStorage, SortedVec},
+    window::Window,
+};
+
+/// Exponentially decayed `Qn` estimator of scale.
+///
+/// Each sample weighs `2^(-age / half_life)`, where the age is the number of samples pushed
+/// after it. So, instead of an abrupt step when an outlier leaves the window, its influence
+/// fades out gradually. The statistic is the weighted order statistic of the pairwise differences,
+/// where a pair weighs the product of the sample weights.
+///
+/// The window still limits the memory usage, so it should span several half-lives.
+#[must_use = "constructing an estimator without using it makes no sense"]
+pub struct DecayedQnScaleEstimator<T> {
+    /// Values along with their sequence numbers.
+    chronological: Window<(T, u64)>,
+
+    /// Values along with their sequence numbers.
+    sorted: SortedVec<(T, u64)>,
+
+    half_life: f64,
+    n_pushed: u64,
+}
+
+impl<T> DecayedQnScaleEstimator<T> {
+    /// Create a new estimator with the specified window length and half-life in samples.
+    ///
+    /// Infinite half-life disables the decay.
+    ///
+    /// # Panics
+    ///
+    /// Zero `window_len`, or non-positive `half_life`.
+    pub fn new(window_len: usize, half_life: f64) -> Self {
+        assert!(half_life > 0.0, "the half-life must be positive");
+        Self {
+            chronological: Window::new(window_len),
+            sorted: SortedVec(Vec::with_capacity(window_len)),
+            half_life,
+            n_pushed: 0,
+        }
+    }
+
+    /// Clear the current sample window.
+    pub fn clear(&mut self) {
+        self.chronological.clear();
+        self.sorted.0.clear();
+    }
+}
+
+impl<T: Copy + PartialOrd> DecayedQnScaleEstimator<T> {
+    /// Push the upcoming value to the estimator.
+    ///
+    /// If the current window is already at its maximum length, the oldest value
+    /// gets discarded.
+    ///
+    /// This operation is `O(window_len)`.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn push(&mut self, value: T) {
+        let item = (value, self.n_pushed);
+        self.n_pushed += 1;
+
+        if let Some(popped_item) = self.chronological.push(item) {
+            self.sorted
+                .remove_value(&popped_item)
+                .expect("the popped value should also be present in the sorted vector");
+        }
+        self.sorted.insert_sorted(item);
+    }
+
+    /// Push multiple values to the estimator.
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>> DecayedQnScaleEstimator<T> {
+    /// Calculate the estimate of scale.
+    ///
+    /// This is an `O(n log^2 n)` operation.
+    ///
+    /// # Returns
+    ///
+    /// Decayed `Qn` estimate of scale, or [`None`] if the window contains no samples.
+    /// The number of samples in the estimate is the Kish effective sample size,
+    /// which is used to choose the order statistic and the normalization constant.
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn estimate(&self) -> Option<ScaleEstimate<T>> {
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+
+        let latest_index = self.n_pushed - 1;
+        #[allow(clippy::cast_precision_loss)]
+        let window: Vec<(T, f64)> = self
+            .sorted
+            .iter()
+            .map(|(value, index)| {
+                let age = (latest_index - index) as f64;
+                (value, (-age / self.half_life).exp2())
+            })
+            .collect();
+        let (sum, sum_of_squares) = window
+            .iter()
+            .fold((0.0, 0.0), |(sum, sum_of_squares), (_, weight)| {
+                (sum + weight, sum_of_squares + weight * weight)
+            });
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let n_samples = ((sum * sum / sum_of_squares).round() as usize).clamp(1, n);
+
+        let statistic = if n_samples == 1 {
+            T::default()
+        } else {
+            let h = n_samples / 2 + 1;
+            #[allow(clippy::cast_precision_loss)]
+            let quantile = (h * (h - 1)) as f64 / (n_samples * (n_samples - 1)) as f64;
+
+            // Same offset as in the unweighted estimator: the negative half and the diagonal
+            // go before the original statistic:
+            let half_weight = sum.mul_add(sum, -sum_of_squares) / 2.0;
+            let k = quantile.mul_add(half_weight, half_weight + sum_of_squares);
+            select_weighted_kth_statistic(&window, k)
+        };
+
+        Some(ScaleEstimate::new(n_samples, statistic, ScaleKind::Qn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QnScaleEstimator;
+
+    #[test]
+    fn empty_ok() {
+        let estimator = DecayedQnScaleEstimator::<i32>::new(1, 1.0);
+        assert_eq!(estimator.estimate(), None);
+    }
+
+    #[test]
+    fn no_decay_ok() {
+        let samples = [
+            2, 78, 1, 4, 19, 37, 68, 91, 42, 42, 75, 40, 4, 18, 18, 77, 9, 78, 57, 99,
+        ];
+
+        let mut estimator = DecayedQnScaleEstimator::new(10, f64::INFINITY);
+        estimator.extend(samples);
+        let mut expected_estimator = QnScaleEstimator::new(10);
+        expected_estimator.extend(samples);
+
+        assert_eq!(estimator.estimate(), expected_estimator.estimate());
+    }
+
+    #[test]
+    fn decay_ok() {
+        let mut estimator = DecayedQnScaleEstimator::new(100, 5.0);
+
+        // The wide regime is getting old:
+        estimator.extend((0..50).map(|i| i % 10 * 10));
+        estimator.extend((0..50).map(|i| i % 10));
+
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.n_samples, 14);
+        assert_eq!(scale.statistic, 2);
+
+        let mut expected_estimator = QnScaleEstimator::new(100);
+        expected_estimator.extend((0..50).map(|i| i % 10 * 10));
+        expected_estimator.extend((0..50).map(|i| i % 10));
+        assert_eq!(expected_estimator.estimate().unwrap().statistic, 5);
+    }
+}
@@ -1,43 +1,222 @@
-use std::{collections::VecDeque, fmt::Debug, ops::Sub};
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    ops::{Add, Sub},
+};
 
+#[cfg(feature = "num-traits")]
+use crate::select::{select_kth_statistics, select_kth_walsh_sum};
 use crate::{
     RawMedian,
-    scale::ScaleEstimate,
-    select::select_kth_statistic,
-    sorted_vec::SortedVec,
-    window::Window,
+    m_scale::{IterativeScaleEstimate, biweight_midvariance, tau_scale},
+    quantile::{QuantileMethod, harrell_davis_quantile, quantile},
+    scale::{ImplosionFallback, ScaleEstimate, ScaleKind},
+    select::{select_kth_of_two_sorted, select_kth_statistic, select_nth},
+    sorted_vec::{SortedStorage, SortedVec},
+    sum_tree::{RangeMoments, SumTree},
+    window::{ChronologicalStorage, Landmark, LateSample, TimedWindow, Window},
 };
 
 /// `Qn` estimator of scale.
+///
+/// `W` defines which samples constitute the current window, see [`QnScaleEstimator::new`]
+/// and [`QnScaleEstimator::with_horizon`].
+///
+/// `S` keeps the window sorted, see [`QnScaleEstimator::with_sorted_storage`].
 #[must_use = "constructing an estimator without using it makes no sense"]
-pub struct QnScaleEstimator<T> {
-    chronological: Window<T>,
-    sorted: SortedVec<T>,
+pub struct QnScaleEstimator<T, W = Window<T>, S = SortedVec<T>> {
+    chronological: W,
+    sorted: S,
+
+    /// Breakdown point, which defines `h` of the generalized `Qn`.
+    breakdown_point: f64,
+
+    implosion_fallback: Option<ImplosionFallback<T>>,
+
+    phantom: PhantomData<T>,
 }
 
 impl<T> QnScaleEstimator<T> {
     /// Create a new estimator with the specified window length.
+    ///
+    /// # Panics
+    ///
+    /// Zero `window_len`.
     pub fn new(window_len: usize) -> Self {
         Self {
-            chronological: Window(VecDeque::with_capacity(window_len)),
+            chronological: Window::new(window_len),
             sorted: SortedVec(Vec::with_capacity(window_len)),
+            breakdown_point: 0.5,
+            implosion_fallback: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> QnScaleEstimator<T, Landmark> {
+    /// Create a new estimator in the landmark mode.
+    ///
+    /// The estimator never evicts anything and accounts for all the samples pushed
+    /// since the last [`QnScaleEstimator::reset_landmark`]. Keep an eye
+    /// on [`QnScaleEstimator::memory_usage`], since the window grows indefinitely.
+    pub const fn landmark() -> Self {
+        Self {
+            chronological: Landmark::new(),
+            sorted: SortedVec(Vec::new()),
+            breakdown_point: 0.5,
+            implosion_fallback: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Ts, D: Default, T> QnScaleEstimator<T, TimedWindow<Ts, D, T>> {
+    /// Create a new estimator with the time-based window.
+    ///
+    /// The window holds the samples whose timestamps are within the `horizon`
+    /// from the watermark (inclusive). By default, no lateness is allowed,
+    /// so the watermark is simply the maximum pushed timestamp.
+    pub fn with_horizon(horizon: D) -> Self {
+        Self {
+            chronological: TimedWindow::new(horizon, D::default()),
+            sorted: SortedVec(Vec::new()),
+            breakdown_point: 0.5,
+            implosion_fallback: None,
+            phantom: PhantomData,
         }
     }
+}
+
+impl<Ts, D, T, S> QnScaleEstimator<T, TimedWindow<Ts, D, T>, S> {
+    /// Accept out-of-order samples, which are at most `allowed_lateness` behind
+    /// the maximum pushed timestamp.
+    ///
+    /// The watermark trails the maximum pushed timestamp by `allowed_lateness`,
+    /// and the window's horizon is counted from the watermark.
+    pub fn with_allowed_lateness(mut self, allowed_lateness: D) -> Self {
+        self.chronological.set_allowed_lateness(allowed_lateness);
+        self
+    }
 
+    /// Number of the samples rejected so far because they were behind the watermark.
+    #[must_use]
+    pub const fn n_late_samples(&self) -> usize {
+        self.chronological.n_late_samples()
+    }
+}
+
+impl<T, W: ChronologicalStorage<T>, S: SortedStorage<T>> QnScaleEstimator<T, W, S> {
     /// Clear the current sample window.
     pub fn clear(&mut self) {
-        self.chronological.0.clear();
-        self.sorted.0.clear();
+        self.chronological.clear();
+        self.sorted.clear();
+    }
+
+    /// Number of the samples in the current window.
+    #[must_use]
+    pub fn n_samples(&self) -> usize {
+        self.sorted.len()
+    }
+
+    /// Estimate the memory occupied by the estimator, in bytes.
+    ///
+    /// This includes the memory allocated on the heap by both the chronological
+    /// and sorted storages.
+    #[must_use]
+    pub fn memory_usage(&self) -> usize {
+        size_of::<Self>() + self.chronological.heap_size() + self.sorted.heap_size()
+    }
+
+    /// Trade the breakdown point for the efficiency.
+    ///
+    /// The estimate becomes the `h(h-1)/2`-th smallest pairwise difference
+    /// with `h = floor(n (1 - breakdown_point)) + 1`, which is at most `n - 1`. The default
+    /// `0.5` gives the original `Qn`, the lower breakdown point gives the higher efficiency
+    /// for the normal distribution.
+    ///
+    /// # Panics
+    ///
+    /// `breakdown_point` is not within `(0, 0.5]`.
+    pub fn with_breakdown_point(mut self, breakdown_point: f64) -> Self {
+        assert!(
+            breakdown_point > 0.0 && breakdown_point <= 0.5,
+            "the breakdown point must be within (0, 0.5]"
+        );
+        self.breakdown_point = breakdown_point;
+        self
+    }
+
+    /// Avoid the zero scale, which is typical for discrete data, where more than half
+    /// of the pairwise differences are zero.
+    ///
+    /// The estimate records the fallback, which the normalization should take into account:
+    /// [`ImplosionFallback::Floor`] always, and the others only when the statistic
+    /// has imploded. See [`ScaleEstimate::applied_fallback`] for whether it has actually
+    /// changed the scale.
+    pub fn with_implosion_fallback(mut self, fallback: ImplosionFallback<T>) -> Self {
+        self.implosion_fallback = Some(fallback);
+        self
+    }
+
+    /// Switch to another sorted storage, keeping the current samples.
+    ///
+    /// The default [`SortedVec`] has the fastest [`QnScaleEstimator::estimate`],
+    /// but `O(window_len)` [`QnScaleEstimator::push`]. [`crate::SkipList`] makes pushing
+    /// `O(log window_len)` at the cost of a slower estimation, which pays off for large windows.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use fqn_estimator::{QnScaleEstimator, SkipList};
+    ///
+    /// let mut estimator = QnScaleEstimator::new(1_000_000).with_sorted_storage::<SkipList<_>>();
+    /// estimator.extend([1, 2, 3]);
+    /// assert_eq!(estimator.estimate().unwrap().statistic, 1);
+    /// ```
+    pub fn with_sorted_storage<S2: SortedStorage<T>>(self) -> QnScaleEstimator<T, W, S2> {
+        let mut sorted = S2::with_capacity(self.sorted.len());
+        for value in self.sorted.iter() {
+            sorted.insert_sorted(value);
+        }
+        QnScaleEstimator {
+            chronological: self.chronological,
+            sorted,
+            breakdown_point: self.breakdown_point,
+            implosion_fallback: self.implosion_fallback,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Take back a previously pushed value, for example, when it turns out to be bogus.
+    ///
+    /// If the window contains several equal values, the most recent one gets retracted.
+    ///
+    /// This operation is `O(window_len)`.
+    ///
+    /// # Returns
+    ///
+    /// The retracted value, or [`None`] if the window contains no such value.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn retract(&mut self, value: &T) -> Option<T>
+    where
+        T: PartialEq,
+    {
+        let retracted_value = self.sorted.remove_value(value)?;
+        assert!(
+            self.chronological.remove_value(value),
+            "the retracted value should also be present in the chronological storage",
+        );
+        Some(retracted_value)
     }
 }
 
-impl<T: Copy + PartialEq + PartialOrd> QnScaleEstimator<T> {
+impl<T: Copy + PartialEq + PartialOrd, S: SortedStorage<T>> QnScaleEstimator<T, Window<T>, S> {
     /// Push the upcoming value to the estimator.
     ///
     /// If the current window is already at its maximum length, the oldest value
     /// gets discarded and will no longer affect the estimator parameters.
     ///
-    /// This operation is `O(window_len)`.
+    /// This operation is `O(window_len)` with [`SortedVec`].
     #[allow(clippy::missing_panics_doc)]
     pub fn push(&mut self, value: T) {
         if let Some(popped_value) = self.chronological.push(value) {
@@ -50,8 +229,48 @@ impl<T: Copy + PartialEq + PartialOrd> QnScaleEstimator<T> {
         // And now, insert the upcoming value into the sorted vector:
         self.sorted.insert_sorted(value);
 
-        debug_assert_eq!(self.sorted.0.capacity(), self.chronological.0.capacity());
-        debug_assert_eq!(self.sorted.0.len(), self.chronological.0.len());
+        debug_assert_eq!(self.sorted.len(), self.chronological.len());
+    }
+
+    /// Push multiple values to the estimator.
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+
+    /// Maximum number of samples in the window.
+    #[must_use]
+    pub const fn window_len(&self) -> usize {
+        self.chronological.max_len()
+    }
+
+    /// Change the window length.
+    ///
+    /// Shrinking the window discards the oldest values, growing it keeps all the current values.
+    ///
+    /// # Panics
+    ///
+    /// Zero `window_len`.
+    pub fn set_window_len(&mut self, window_len: usize) {
+        self.chronological.set_max_len(window_len);
+        while let Some(popped_value) = self.chronological.pop_excess() {
+            self.sorted
+                .remove_value(&popped_value)
+                .expect("the popped value should also be present in the sorted vector");
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd, S: SortedStorage<T>> QnScaleEstimator<T, Landmark, S> {
+    /// Push the upcoming value to the estimator.
+    ///
+    /// Nothing ever gets evicted in the landmark mode.
+    ///
+    /// This operation is `O(n)` with [`SortedVec`].
+    pub fn push(&mut self, value: T) {
+        self.chronological.push();
+        self.sorted.insert_sorted(value);
     }
 
     /// Push multiple values to the estimator.
@@ -62,7 +281,68 @@ impl<T: Copy + PartialEq + PartialOrd> QnScaleEstimator<T> {
     }
 }
 
-impl<T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>> QnScaleEstimator<T> {
+impl<T, S: SortedStorage<T>> QnScaleEstimator<T, Landmark, S> {
+    /// Drop all the samples and start a new epoch.
+    pub fn reset_landmark(&mut self) {
+        self.chronological.reset();
+        self.sorted.clear();
+    }
+
+    /// Number of the landmarks set by [`QnScaleEstimator::reset_landmark`] so far.
+    #[must_use]
+    pub const fn epoch(&self) -> u64 {
+        self.chronological.epoch()
+    }
+}
+
+impl<Ts, D, T, S> QnScaleEstimator<T, TimedWindow<Ts, D, T>, S>
+where
+    Ts: Copy + PartialOrd + Sub<Ts, Output = D>,
+    D: Copy + PartialOrd + Add<D, Output = D>,
+    T: Copy + PartialEq + PartialOrd,
+    S: SortedStorage<T>,
+{
+    /// Push the upcoming timestamped value to the estimator.
+    ///
+    /// Samples may arrive out of order, as long as they are not behind the watermark.
+    /// The values older than the horizon relatively to the watermark get discarded
+    /// and will no longer affect the estimator parameters.
+    ///
+    /// This operation is `O(window_len)` per each added or discarded value with [`SortedVec`].
+    ///
+    /// # Errors
+    ///
+    /// [`LateSample`] if the sample is behind the watermark. Such a sample is rejected
+    /// and counted in [`QnScaleEstimator::n_late_samples`].
+    #[allow(clippy::missing_panics_doc)]
+    pub fn push(&mut self, timestamp: Ts, value: T) -> Result<(), LateSample<Ts, T>> {
+        self.chronological.push(timestamp, value)?;
+        while let Some(popped_value) = self.chronological.pop_expired() {
+            self.sorted
+                .remove_value(&popped_value)
+                .expect("the popped value should also be present in the sorted vector");
+        }
+        self.sorted.insert_sorted(value);
+
+        debug_assert_eq!(self.sorted.len(), self.chronological.len());
+        Ok(())
+    }
+
+    /// Push multiple timestamped values to the estimator.
+    ///
+    /// Late samples are skipped, but still counted in [`QnScaleEstimator::n_late_samples`].
+    pub fn extend(&mut self, iter: impl IntoIterator<Item = (Ts, T)>) {
+        for (timestamp, value) in iter {
+            let _ = self.push(timestamp, value);
+        }
+    }
+}
+
+impl<T, W, S> QnScaleEstimator<T, W, S>
+where
+    T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>,
+    S: SortedStorage<T>,
+{
     /// Calculate the estimate of scale.
     ///
     /// This is a linear-time operation.
@@ -70,36 +350,304 @@ impl<T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>> QnScaleEstimat
     /// # Returns
     ///
     /// Qn estimate of scale, or [`None`] if the sample contains no samples.
+    /// With a custom breakdown point, the estimate is [`ScaleKind::GeneralizedQn`].
+    #[allow(clippy::missing_panics_doc)]
     #[must_use = "calculating the scale without using it makes no sense"]
     pub fn estimate(&self) -> Option<ScaleEstimate<T>> {
-        let n = self.sorted.0.len();
+        let n = self.sorted.len();
         if n == 0 {
             return None;
         }
 
+        let h = self.h(n);
+
         let statistic = if n == 1 {
             T::default()
         } else {
-            let h = n / 2 + 1;
-            let k =
-            // From original Qn estimator:
-            h * (h - 1) / 2
-            // Offset to express the original statistic in terms of the `X + (-X)` statistic:
-            + n + n * (n - 1) / 2;
-            select_kth_statistic(self.sorted.0.iter().copied(), k)
+            self.pairwise_difference(h * (h - 1) / 2)
+                .expect("the Qn rank should be within the pairs")
         };
 
-        Some(ScaleEstimate {
+        #[allow(clippy::float_cmp)]
+        let kind = if self.breakdown_point == 0.5 {
+            ScaleKind::Qn
+        } else {
+            ScaleKind::GeneralizedQn { h }
+        };
+        let mut estimate = ScaleEstimate {
             n_samples: n,
             statistic,
-        })
+            kind,
+            fallback: None,
+        };
+        let imploded = statistic == T::default();
+        match self.implosion_fallback {
+            Some(fallback @ ImplosionFallback::NextNonZeroDifference) if imploded => {
+                if let Some(statistic) = self.pairwise_difference(self.n_zero_differences() + 1) {
+                    estimate.statistic = statistic;
+                    estimate.fallback = Some(fallback);
+                }
+            }
+            Some(fallback @ ImplosionFallback::Resolution(_)) if imploded => {
+                estimate.fallback = Some(fallback);
+            }
+            Some(fallback @ ImplosionFallback::Floor(_)) => {
+                estimate.fallback = Some(fallback);
+            }
+            _ => {}
+        }
+        Some(estimate)
+    }
+
+    /// Calculate the Rousseeuw–Croux `Sn` estimate of scale: `lomed_i himed_j |x_i - x_j|`.
+    ///
+    /// It has the same 50% breakdown point as `Qn`.
+    ///
+    /// This is an `O(n log n)` operation.
+    ///
+    /// # Returns
+    ///
+    /// Sn estimate of scale, or [`None`] if the window contains no samples.
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn sn(&self) -> Option<ScaleEstimate<T>> {
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+
+        let statistic = if n == 1 {
+            T::default()
+        } else {
+            let sorted: Vec<T> = self.sorted.iter().collect();
+            let mut inner_medians: Vec<T> =
+                (0..n).map(|index| himed_distance(&sorted, index)).collect();
+            select_nth(&mut inner_medians, n.div_ceil(2) - 1)
+        };
+
+        Some(ScaleEstimate::new(n, statistic, ScaleKind::Sn))
+    }
+
+    /// Count the pairs of equal samples.
+    ///
+    /// This is a linear-time operation.
+    fn n_zero_differences(&self) -> usize {
+        let mut n_zero_differences = 0;
+        let mut run_len = 0;
+        let mut previous = None;
+        for value in self.sorted.iter() {
+            if previous == Some(value) {
+                run_len += 1;
+            } else {
+                run_len = 0;
+            }
+            n_zero_differences += run_len;
+            previous = Some(value);
+        }
+        n_zero_differences
+    }
+
+    /// Calculate `h` for the specified number of samples, see [`QnScaleEstimator::with_breakdown_point`].
+    fn h(&self, n: usize) -> usize {
+        #[allow(clippy::float_cmp)]
+        if self.breakdown_point == 0.5 || n <= 2 {
+            return n / 2 + 1;
+        }
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let h = (n as f64 * (1.0 - self.breakdown_point)).floor() as usize + 1;
+        h.min(n - 1)
+    }
+
+    /// Select the `rank`-th smallest of the `n(n-1)/2` pairwise absolute differences
+    /// `|x_i - x_j|`, `i < j`.
+    ///
+    /// **Note, that `rank` starts from `1`.**
+    ///
+    /// This is a linear-time operation.
+    ///
+    /// # Returns
+    ///
+    /// The difference, or [`None`] if `rank` is out of the pairs range.
+    #[must_use]
+    pub fn pairwise_difference(&self, rank: usize) -> Option<T> {
+        let n = self.sorted.len();
+        let n_pairs = n * n.saturating_sub(1) / 2;
+        if !(1..=n_pairs).contains(&rank) {
+            return None;
+        }
+
+        // Offset to express the statistic in terms of the `X + (-X)` statistic:
+        // skip the negative differences and the diagonal.
+        let k = rank + n + n_pairs;
+        Some(select_kth_statistic(self.sorted.iter(), k))
+    }
+
+    /// Select the `p`-quantile of the pairwise absolute differences `|x_i - x_j|`, `i < j`.
+    ///
+    /// This is the inverse of the empirical distribution function, that is the smallest difference,
+    /// which is not less than the `p` fraction of all the differences. Like in [`Self::quantile`],
+    /// the rank is fuzzed for the floating-point errors.
+    ///
+    /// This is a linear-time operation.
+    ///
+    /// # Returns
+    ///
+    /// The difference, or [`None`] if the window contains less than two samples.
+    ///
+    /// # Panics
+    ///
+    /// `p` is not within `[0, 1]`.
+    #[must_use]
+    pub fn pairwise_difference_quantile(&self, p: f64) -> Option<T> {
+        const FUZZ: f64 = 4.0 * f64::EPSILON;
+
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "the probability must be within [0, 1]"
+        );
+        let n = self.sorted.len();
+        let n_pairs = n * n.saturating_sub(1) / 2;
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_precision_loss,
+            clippy::cast_sign_loss
+        )]
+        let rank = {
+            // The rounding error of `p` grows with the number of pairs:
+            let n_pairs = n_pairs as f64;
+            p.mul_add(n_pairs, -FUZZ * n_pairs).ceil() as usize
+        };
+        self.pairwise_difference(rank.clamp(1, n_pairs.max(1)))
     }
 }
 
-impl<T: Copy> QnScaleEstimator<T> {
+#[cfg(feature = "num-traits")]
+impl<T, W, S> QnScaleEstimator<T, W, S>
+where
+    T: Copy + PartialOrd + Add<T, Output = T> + Sub<T, Output = T>,
+    T: std::ops::Div<T, Output = T> + num_traits::One,
+    S: SortedStorage<T>,
+{
+    /// Calculate the median absolute deviation from the median.
+    ///
+    /// The deviations below and above the median are both sorted, so the selection
+    /// takes `O(log n)` steps instead of sorting the deviations. Each step is a constant-time
+    /// operation with [`SortedVec`].
+    ///
+    /// # Returns
+    ///
+    /// MAD estimate of scale, or [`None`] if the window contains no samples.
+    #[allow(clippy::missing_panics_doc)]
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn mad(&self) -> Option<ScaleEstimate<T>> {
+        let n = self.sorted.len();
+        let center = self.sorted.median()?.to_median();
+        let n_left = self.sorted.partition_point(|value| *value < center);
+        let get = |index| {
+            self.sorted
+                .get(index)
+                .expect("the index should be within the sorted storage")
+        };
+        let deviation = |k| {
+            select_kth_of_two_sorted(
+                |rank| center - get(n_left - 1 - rank),
+                n_left,
+                |rank| get(n_left + rank) - center,
+                n - n_left,
+                k,
+            )
+        };
+
+        let statistic = if n & 1 == 1 {
+            RawMedian::Odd(deviation(n / 2 + 1))
+        } else {
+            RawMedian::Even(deviation(n / 2), deviation(n / 2 + 1))
+        };
+        Some(ScaleEstimate::new(n, statistic.to_median(), ScaleKind::Mad))
+    }
+
+    /// Calculate the Shamos estimate of scale, that is the median of `|x_i - x_j|` for `i < j`.
+    ///
+    /// It is more efficient than `Qn` for the normal distribution, but its breakdown point
+    /// is only 29%.
+    ///
+    /// This is a linear-time operation.
+    ///
+    /// # Returns
+    ///
+    /// Shamos estimate of scale, or [`None`] if the window contains no samples.
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn shamos(&self) -> Option<ScaleEstimate<T>>
+    where
+        T: Debug + Default,
+    {
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+
+        let statistic = if n == 1 {
+            T::default()
+        } else {
+            // Offset to express the statistic in terms of the `X + (-X)` statistic:
+            let offset = n + n * (n - 1) / 2;
+            let n_differences = n * (n - 1) / 2;
+            let (upper, lower) = select_kth_statistics(
+                self.sorted.iter(),
+                offset + n_differences / 2 + 1,
+                offset + n_differences.div_ceil(2),
+            );
+            if n_differences & 1 == 1 {
+                upper
+            } else {
+                RawMedian::Even(lower, upper).to_median()
+            }
+        };
+
+        Some(ScaleEstimate::new(n, statistic, ScaleKind::Shamos))
+    }
+
+    /// Calculate the Hodges–Lehmann estimate of location, that is the median of the Walsh
+    /// averages `(x_i + x_j) / 2` for `i <= j`.
+    ///
+    /// For the integer samples, the result is truncated twice: the two middle sums get averaged,
+    /// and then halved.
+    ///
+    /// This is a linear-time operation.
+    ///
+    /// # Returns
+    ///
+    /// The estimate, or [`None`] if the window contains no samples.
+    #[must_use]
+    pub fn hodges_lehmann(&self) -> Option<T>
+    where
+        T: Debug,
+    {
+        let n = self.sorted.len();
+        if n <= 1 {
+            return self.sorted.get(0);
+        }
+
+        let n_sums = n * (n + 1) / 2;
+        let upper = select_kth_walsh_sum(self.sorted.iter(), n_sums / 2 + 1);
+        let sum = if n_sums & 1 == 1 {
+            RawMedian::Odd(upper)
+        } else {
+            RawMedian::Even(select_kth_walsh_sum(self.sorted.iter(), n_sums / 2), upper)
+        };
+        Some(sum.to_median() / (T::one() + T::one()))
+    }
+}
+
+impl<T: Copy, W, S: SortedStorage<T>> QnScaleEstimator<T, W, S> {
     /// Obtain the sample median.
     ///
-    /// This is a constant-time operation.
+    /// This is a constant-time operation with [`SortedVec`].
     ///
     /// # Returns
     ///
@@ -110,9 +658,312 @@ impl<T: Copy> QnScaleEstimator<T> {
     }
 }
 
+impl<T: Copy + Into<f64>, W, S: SortedStorage<T>> QnScaleEstimator<T, W, S> {
+    /// Calculate the sample `p`-quantile, matching R's `quantile()` and `numpy.quantile`.
+    ///
+    /// This is a constant-time operation with [`SortedVec`].
+    ///
+    /// # Returns
+    ///
+    /// The sample quantile, or [`None`] – if the sample is empty.
+    ///
+    /// # Panics
+    ///
+    /// `p` is not within `[0, 1]`.
+    #[must_use]
+    pub fn quantile(&self, p: f64, method: QuantileMethod) -> Option<f64> {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "the quantile must be within [0, 1]"
+        );
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+        Some(quantile(|index| self.get(index), n, p, method))
+    }
+
+    /// Calculate the Harrell–Davis estimate of the `p`-quantile.
+    ///
+    /// It is the weighted sum of all the order statistics, which is much more efficient
+    /// than a single order statistic for small samples. With `p = 0.5`, it is a location estimate,
+    /// which could be used instead of [`QnScaleEstimator::median`].
+    ///
+    /// This evaluates the Beta distribution function `n` times.
+    ///
+    /// # Returns
+    ///
+    /// The quantile estimate, or [`None`] – if the sample is empty.
+    ///
+    /// # Panics
+    ///
+    /// `p` is not within `[0, 1]`.
+    #[must_use]
+    pub fn harrell_davis_quantile(&self, p: f64) -> Option<f64> {
+        self.harrell_davis_quantile_impl(p, false)
+    }
+
+    /// Calculate the trimmed Harrell–Davis estimate of the `p`-quantile.
+    ///
+    /// Only the order statistics within the highest density interval of width `1 / sqrt(n)`
+    /// of the weighting Beta distribution are used, which makes it more robust,
+    /// keeping most of the efficiency.
+    ///
+    /// This evaluates the Beta distribution function `O(sqrt(n))` times.
+    ///
+    /// # Returns
+    ///
+    /// The quantile estimate, or [`None`] – if the sample is empty.
+    ///
+    /// # Panics
+    ///
+    /// `p` is not within `[0, 1]`.
+    #[must_use]
+    pub fn trimmed_harrell_davis_quantile(&self, p: f64) -> Option<f64> {
+        self.harrell_davis_quantile_impl(p, true)
+    }
+
+    fn harrell_davis_quantile_impl(&self, p: f64, trimmed: bool) -> Option<f64> {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "the quantile must be within [0, 1]"
+        );
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+        Some(harrell_davis_quantile(
+            |index| self.get(index),
+            n,
+            p,
+            trimmed,
+        ))
+    }
+
+    /// Get the `index`-th smallest sample (starting from `0`).
+    fn get(&self, index: usize) -> f64 {
+        self.sorted
+            .get(index)
+            .expect("the index should be within the sample")
+            .into()
+    }
+
+    /// Calculate the interquartile range, that is the difference between the third
+    /// and the first quartiles.
+    ///
+    /// # Returns
+    ///
+    /// The interquartile range, or [`None`] – if the sample is empty.
+    #[must_use]
+    pub fn iqr(&self, method: QuantileMethod) -> Option<f64> {
+        Some(self.quantile(0.75, method)? - self.quantile(0.25, method)?)
+    }
+}
+
+impl<T, W, S> QnScaleEstimator<T, W, S>
+where
+    T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T> + Into<f64>,
+    S: SortedStorage<T>,
+{
+    /// Calculate the biweight midvariance, iterating from [`QnScaleEstimator::median`]
+    /// and [`QnScaleEstimator::estimate`].
+    ///
+    /// Each iteration updates the location with the biweight location, and the scale
+    /// with the square root of the biweight midvariance. The tuning constant `c` is expressed
+    /// in the units of the raw median absolute deviation, the conventional choice is `9`.
+    ///
+    /// Each iteration is `O(n)`.
+    ///
+    /// # Returns
+    ///
+    /// The refined location and scale, or [`None`] – if the sample is empty.
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn biweight_midvariance(
+        &self,
+        c: f64,
+        max_iterations: usize,
+    ) -> Option<IterativeScaleEstimate> {
+        let (location, scale) = self.seed()?;
+        Some(biweight_midvariance(
+            &self.sorted.iter().map(Into::into).collect::<Vec<_>>(),
+            location,
+            scale,
+            c,
+            max_iterations,
+        ))
+    }
+
+    /// Calculate the Yohai–Zamar τ-scale, iterating from [`QnScaleEstimator::median`]
+    /// and [`QnScaleEstimator::estimate`].
+    ///
+    /// Each iteration updates the location with the weighted mean, and the scale
+    /// with the truncated quadratic M-scale. The tuning constants are expressed in the units
+    /// of the raw median absolute deviation, the conventional choice is `c1 = 4.5`
+    /// and `c2 = 3`. With a single iteration, it is the original τ-scale.
+    ///
+    /// Each iteration is `O(n)`.
+    ///
+    /// # Returns
+    ///
+    /// The refined location and scale, or [`None`] – if the sample is empty.
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn tau_scale(
+        &self,
+        c1: f64,
+        c2: f64,
+        max_iterations: usize,
+    ) -> Option<IterativeScaleEstimate> {
+        let (location, scale) = self.seed()?;
+        Some(tau_scale(
+            &self.sorted.iter().map(Into::into).collect::<Vec<_>>(),
+            location,
+            scale,
+            c1,
+            c2,
+            max_iterations,
+        ))
+    }
+
+    /// Median and normalized scale.
+    pub(crate) fn seed(&self) -> Option<(f64, f64)> {
+        Some((self.median()?.into(), self.estimate()?.into()))
+    }
+}
+
+impl<T: Copy + PartialOrd + Into<f64>, W> QnScaleEstimator<T, W, SumTree<T>> {
+    /// Calculate the α-trimmed mean, that is the mean of the samples without
+    /// the `floor(αn)` smallest and `floor(αn)` largest ones.
+    ///
+    /// This is an `O(log n)` operation.
+    ///
+    /// # Returns
+    ///
+    /// The trimmed mean, or [`None`] – if the sample is empty.
+    ///
+    /// # Panics
+    ///
+    /// `alpha` is not within `[0, 0.5)`.
+    #[must_use]
+    pub fn trimmed_mean(&self, alpha: f64) -> Option<f64> {
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+        let n_trimmed = n_trimmed(n, alpha);
+        Some(self.sorted.range_moments(n_trimmed..n - n_trimmed).mean)
+    }
+
+    /// Calculate the α-winsorized mean, that is the mean of the samples, where
+    /// the `floor(αn)` smallest and `floor(αn)` largest ones are replaced
+    /// with the nearest remaining ones.
+    ///
+    /// This is an `O(log n)` operation.
+    ///
+    /// # Returns
+    ///
+    /// The winsorized mean, or [`None`] – if the sample is empty.
+    ///
+    /// # Panics
+    ///
+    /// `alpha` is not within `[0, 0.5)`.
+    #[must_use]
+    pub fn winsorized_mean(&self, alpha: f64) -> Option<f64> {
+        let n = self.sorted.len();
+        if n == 0 {
+            return None;
+        }
+        Some(self.winsorized_moments(alpha).mean)
+    }
+
+    /// Calculate the α-winsorized sample variance, see [`QnScaleEstimator::winsorized_mean`].
+    ///
+    /// This is an `O(log n)` operation.
+    ///
+    /// # Returns
+    ///
+    /// The winsorized variance with the `n - 1` denominator, or [`None`] – if the sample
+    /// contains less than two samples.
+    ///
+    /// # Panics
+    ///
+    /// `alpha` is not within `[0, 0.5)`.
+    #[must_use]
+    pub fn winsorized_variance(&self, alpha: f64) -> Option<f64> {
+        let n = self.sorted.len();
+        if n < 2 {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        Some(self.winsorized_moments(alpha).sum_of_squared_deviations / (n - 1) as f64)
+    }
+
+    /// Moments of the winsorized non-empty sample.
+    fn winsorized_moments(&self, alpha: f64) -> RangeMoments {
+        let n = self.sorted.len();
+        let n_trimmed = n_trimmed(n, alpha);
+        let mut moments = self.sorted.range_moments(n_trimmed..n - n_trimmed);
+        for index in [n_trimmed, n - n_trimmed - 1] {
+            let value: f64 = self
+                .sorted
+                .get(index)
+                .expect("the index should be within the sample")
+                .into();
+            moments = moments.merge(RangeMoments::repeat(value, n_trimmed));
+        }
+        moments
+    }
+}
+
+/// Number of the samples to trim from each side.
+///
+/// # Panics
+///
+/// `alpha` is not within `[0, 0.5)`.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn n_trimmed(n: usize, alpha: f64) -> usize {
+    assert!(
+        (0.0..0.5).contains(&alpha),
+        "the trimmed proportion must be within [0, 0.5)"
+    );
+    // The floating-point error must not trim the middle sample:
+    ((alpha * n as f64).floor() as usize).min((n - 1) / 2)
+}
+
+/// Calculate `himed_j |x_i - x_j|` for the sorted sample in `O(log n)`.
+fn himed_distance<T>(sorted: &[T], index: usize) -> T
+where
+    T: Copy + PartialOrd + Sub<T, Output = T>,
+{
+    let value = sorted[index];
+
+    // The zero distance to itself is the smallest one, so the high median of `n` distances
+    // is the `n / 2`-th one among the rest:
+    select_kth_of_two_sorted(
+        |rank| value - sorted[index - 1 - rank],
+        index,
+        |rank| sorted[index + 1 + rank] - value,
+        sorted.len() - 1 - index,
+        sorted.len() / 2,
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::QnScaleEstimator;
+    use crate::{
+        ImplosionFallback,
+        Normalization,
+        QnScaleEstimator,
+        QuantileMethod,
+        RawMedian,
+        ScaleKind,
+        SkipList,
+        SortedStorage,
+        SumTree,
+    };
 
     #[test]
     fn empty_ok() {
@@ -154,4 +1005,535 @@ mod tests {
         assert_eq!(scale.n_samples, 10);
         assert_eq!(scale.statistic, 22);
     }
+
+    #[test]
+    fn horizon_ok() {
+        let mut estimator = QnScaleEstimator::with_horizon(10_u32);
+        estimator.extend([(0_u32, 100), (1, 1), (6, 2), (11, 4)]);
+
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.n_samples, 3);
+        assert_eq!(scale.statistic, 1);
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(2)));
+    }
+
+    #[test]
+    fn allowed_lateness_ok() {
+        let mut estimator = QnScaleEstimator::with_horizon(10_u32).with_allowed_lateness(2);
+        estimator.push(10_u32, 1).unwrap();
+        estimator.push(9, 2).unwrap();
+        assert!(estimator.push(7, 3).is_err());
+        assert_eq!(estimator.n_late_samples(), 1);
+
+        // Watermark is at 19, the sample at 9 is still within the horizon:
+        estimator.push(21, 3).unwrap();
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(2)));
+
+        // Watermark is at 20, the sample at 9 is now evicted:
+        estimator.push(22, 4).unwrap();
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(3)));
+    }
+
+    #[test]
+    fn skip_list_ok() {
+        let samples = [
+            2, 78, 1, 4, 19, 37, 68, 91, 42, 42, 75, 40, 4, 18, 18, 77, 9, 78, 57, 99,
+        ];
+
+        let mut estimator = QnScaleEstimator::new(10).with_sorted_storage::<SkipList<_>>();
+        estimator.extend(samples);
+
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.n_samples, 10);
+        assert_eq!(scale.statistic, 22);
+    }
+
+    #[test]
+    fn set_window_len_ok() {
+        let mut estimator = QnScaleEstimator::new(4);
+        estimator.extend([1, 2, 4, 8]);
+
+        estimator.set_window_len(2);
+        assert_eq!(estimator.window_len(), 2);
+        assert_eq!(estimator.median(), Some(RawMedian::Even(4, 8)));
+
+        estimator.set_window_len(3);
+        estimator.push(16);
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(8)));
+        estimator.push(32);
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(16)));
+    }
+
+    #[test]
+    #[should_panic = "the window length must be positive"]
+    fn zero_window_len_panics() {
+        let _ = QnScaleEstimator::<i32>::new(0);
+    }
+
+    #[test]
+    fn landmark_ok() {
+        let mut estimator = QnScaleEstimator::landmark();
+        estimator.extend(1..=1000);
+        assert_eq!(estimator.median(), Some(RawMedian::Even(500, 501)));
+        assert!(estimator.memory_usage() >= 1000 * size_of::<i32>());
+
+        estimator.reset_landmark();
+        assert_eq!(estimator.epoch(), 1);
+        assert_eq!(estimator.estimate(), None);
+
+        estimator.push(42);
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(42)));
+    }
+
+    #[test]
+    fn retract_ok() {
+        let mut estimator = QnScaleEstimator::new(3);
+        estimator.extend([1, 1000, 2, 3]);
+        assert_eq!(
+            estimator.retract(&1),
+            None,
+            "the value should be evicted already"
+        );
+        assert_eq!(estimator.retract(&1000), Some(1000));
+        assert_eq!(estimator.median(), Some(RawMedian::Even(2, 3)));
+
+        // The window is not full anymore:
+        estimator.push(4);
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(3)));
+    }
+
+    #[test]
+    fn retract_timed_ok() {
+        let mut estimator = QnScaleEstimator::with_horizon(10_u32);
+        estimator.extend([(0_u32, 1), (1, 2), (2, 1)]);
+        assert_eq!(estimator.retract(&1), Some(1));
+
+        // The retracted sample is the most recent one, so the older one still gets evicted:
+        estimator.push(11, 3).unwrap();
+        assert_eq!(estimator.median(), Some(RawMedian::Even(2, 3)));
+    }
+
+    /// Compare against the naïve implementation.
+    #[test]
+    #[cfg(feature = "num-traits")]
+    #[allow(clippy::float_cmp)]
+    fn mad_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let samples: Vec<f64> = (0..rng.usize(1..50))
+                .map(|_| f64::from(rng.i32(0..100)))
+                .collect();
+            let mut estimator = QnScaleEstimator::new(samples.len());
+            estimator.extend(samples.iter().copied());
+
+            let mut sorted = samples.clone();
+            sorted.sort_unstable_by(f64::total_cmp);
+            let median = |sorted: &[f64]| {
+                let n = sorted.len();
+                (sorted[(n - 1) / 2] + sorted[n / 2]) / 2.0
+            };
+            let center = median(&sorted);
+            let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - center).abs()).collect();
+            deviations.sort_unstable_by(f64::total_cmp);
+
+            let scale = estimator.mad().unwrap();
+            assert_eq!(scale.statistic, median(&deviations), "samples: {samples:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "num-traits")]
+    #[allow(clippy::float_cmp)]
+    fn mad_normalization_ok() {
+        let mut estimator = QnScaleEstimator::new(20);
+        estimator.extend((1..=20).map(f64::from));
+        assert_eq!(estimator.mad().unwrap().statistic, 5.0);
+        let scale: f64 = estimator.mad().unwrap().into();
+        assert!(7.721 < scale && scale < 7.722, "scale: {scale}");
+    }
+
+    /// Compare against the naïve quadratic implementation.
+    #[test]
+    fn sn_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let samples: Vec<i32> = (0..rng.usize(2..50)).map(|_| rng.i32(0..100)).collect();
+            let n = samples.len();
+            let mut estimator = QnScaleEstimator::new(n).with_sorted_storage::<SkipList<_>>();
+            estimator.extend(samples.iter().copied());
+
+            let mut inner_medians: Vec<i32> = samples
+                .iter()
+                .map(|lhs| {
+                    let mut distances: Vec<i32> =
+                        samples.iter().map(|rhs| (lhs - rhs).abs()).collect();
+                    distances.sort_unstable();
+                    distances[n / 2]
+                })
+                .collect();
+            inner_medians.sort_unstable();
+
+            let scale = estimator.sn().unwrap();
+            assert_eq!(scale.n_samples, n);
+            assert_eq!(scale.kind, ScaleKind::Sn);
+            assert_eq!(
+                scale.statistic,
+                inner_medians[n.div_ceil(2) - 1],
+                "samples: {samples:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sn_edge_cases_ok() {
+        let mut estimator = QnScaleEstimator::landmark();
+        assert_eq!(estimator.sn(), None);
+        estimator.push(42);
+        assert_eq!(estimator.sn().unwrap().statistic, 0);
+    }
+
+    #[test]
+    fn sn_normalization_ok() {
+        let mut estimator = QnScaleEstimator::new(10);
+        estimator.extend((1..=10).map(f64::from));
+        let scale: f64 = estimator.sn().unwrap().into();
+        assert!(3.603 < scale && scale < 3.604, "scale: {scale}");
+    }
+
+    /// Compare against the naïve implementation.
+    #[test]
+    #[cfg(feature = "num-traits")]
+    #[allow(clippy::float_cmp)]
+    fn hodges_lehmann_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let samples: Vec<f64> = (0..rng.usize(1..50))
+                .map(|_| f64::from(rng.i32(0..100)))
+                .collect();
+            let mut estimator = QnScaleEstimator::new(samples.len());
+            estimator.extend(samples.iter().copied());
+
+            let mut averages: Vec<f64> = samples
+                .iter()
+                .enumerate()
+                .flat_map(|(i, lhs)| samples[i..].iter().map(move |rhs| (lhs + rhs) / 2.0))
+                .collect();
+            averages.sort_unstable_by(f64::total_cmp);
+            let n = averages.len();
+            let expected = (averages[(n - 1) / 2] + averages[n / 2]) / 2.0;
+
+            assert_eq!(
+                estimator.hodges_lehmann(),
+                Some(expected),
+                "samples: {samples:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "num-traits")]
+    fn hodges_lehmann_walsh_averages_ok() {
+        let mut estimator = QnScaleEstimator::new(3);
+        estimator.extend([0.0, 1.0, 10.0]);
+        assert_eq!(estimator.hodges_lehmann(), Some(3.0));
+    }
+
+    /// Compare against the naïve implementation.
+    #[test]
+    #[cfg(feature = "num-traits")]
+    #[allow(clippy::float_cmp)]
+    fn shamos_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let samples: Vec<f64> = (0..rng.usize(2..50))
+                .map(|_| f64::from(rng.i32(0..100)))
+                .collect();
+            let mut estimator = QnScaleEstimator::new(samples.len());
+            estimator.extend(samples.iter().copied());
+
+            let mut differences: Vec<f64> = samples
+                .iter()
+                .enumerate()
+                .flat_map(|(i, lhs)| samples[i + 1..].iter().map(move |rhs| (lhs - rhs).abs()))
+                .collect();
+            differences.sort_unstable_by(f64::total_cmp);
+            let n = differences.len();
+            let expected = (differences[(n - 1) / 2] + differences[n / 2]) / 2.0;
+
+            let scale = estimator.shamos().unwrap();
+            assert_eq!(scale.statistic, expected, "samples: {samples:?}");
+        }
+    }
+
+    /// Compare against the naïve implementation.
+    #[test]
+    fn pairwise_difference_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let samples: Vec<i32> = (0..rng.usize(2..30)).map(|_| rng.i32(0..100)).collect();
+            let mut estimator = QnScaleEstimator::new(samples.len());
+            estimator.extend(samples.iter().copied());
+
+            let mut differences: Vec<i32> = samples
+                .iter()
+                .enumerate()
+                .flat_map(|(i, lhs)| samples[i + 1..].iter().map(move |rhs| (lhs - rhs).abs()))
+                .collect();
+            differences.sort_unstable();
+
+            assert_eq!(estimator.pairwise_difference(0), None);
+            assert_eq!(estimator.pairwise_difference(differences.len() + 1), None);
+            for (index, expected) in differences.iter().enumerate() {
+                assert_eq!(estimator.pairwise_difference(index + 1), Some(*expected));
+            }
+            assert_eq!(
+                estimator.pairwise_difference_quantile(0.0),
+                differences.first().copied()
+            );
+            assert_eq!(
+                estimator.pairwise_difference_quantile(1.0),
+                differences.last().copied()
+            );
+        }
+    }
+
+    #[test]
+    fn pairwise_difference_quantile_ok() {
+        let mut estimator = QnScaleEstimator::new(5);
+        assert_eq!(estimator.pairwise_difference_quantile(0.5), None);
+
+        // Differences: 1, 1, 1, 1, 2, 2, 2, 3, 3, 4.
+        estimator.extend([1, 2, 3, 4, 5]);
+        assert_eq!(estimator.pairwise_difference_quantile(0.1), Some(1));
+        assert_eq!(estimator.pairwise_difference_quantile(0.5), Some(2));
+        assert_eq!(estimator.pairwise_difference_quantile(0.9), Some(3));
+
+        // Differences: 1, 2, 3, 4, 6, 7, 8, 12, 14, 15.
+        estimator.extend([0, 1, 3, 7, 15]);
+        assert_eq!(estimator.pairwise_difference_quantile(0.1 + 0.2), Some(3));
+    }
+
+    /// `(0.1 + 0.2) * 1,999,000` overshoots `599,700` by far more than the machine epsilon.
+    #[test]
+    fn pairwise_difference_quantile_large_window_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let samples: Vec<i64> = (0..2000).map(|_| rng.i64(0..1_000_000_000)).collect();
+        let mut estimator = QnScaleEstimator::new(samples.len());
+        estimator.extend(samples.iter().copied());
+
+        let mut differences: Vec<i64> = samples
+            .iter()
+            .enumerate()
+            .flat_map(|(i, lhs)| samples[i + 1..].iter().map(move |rhs| (lhs - rhs).abs()))
+            .collect();
+        differences.sort_unstable();
+        assert_eq!(differences.len(), 1_999_000);
+        assert_ne!(differences[599_699], differences[599_700]);
+
+        assert_eq!(
+            estimator.pairwise_difference_quantile(0.1 + 0.2),
+            Some(differences[599_699])
+        );
+    }
+
+    /// Compare against the naïve implementation.
+    #[test]
+    fn breakdown_point_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let samples: Vec<i32> = (0..rng.usize(3..30)).map(|_| rng.i32(0..100)).collect();
+            let n = samples.len();
+            let mut estimator = QnScaleEstimator::new(n).with_breakdown_point(0.25);
+            estimator.extend(samples.iter().copied());
+
+            let mut differences: Vec<i32> = samples
+                .iter()
+                .enumerate()
+                .flat_map(|(i, lhs)| samples[i + 1..].iter().map(move |rhs| (lhs - rhs).abs()))
+                .collect();
+            differences.sort_unstable();
+
+            let h = (n * 3 / 4 + 1).min(n - 1);
+            let scale = estimator.estimate().unwrap();
+            assert_eq!(scale.kind, ScaleKind::GeneralizedQn { h });
+            assert_eq!(scale.statistic, differences[h * (h - 1) / 2 - 1]);
+        }
+    }
+
+    #[test]
+    fn quantile_ok() {
+        let mut estimator = QnScaleEstimator::new(10);
+        assert_eq!(estimator.quantile(0.5, QuantileMethod::Linear), None);
+
+        // Window contains 1..=10:
+        estimator.extend([12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1].map(f64::from));
+        assert_eq!(estimator.quantile(0.25, QuantileMethod::Linear), Some(3.25));
+        assert_eq!(
+            estimator.quantile(0.9, QuantileMethod::InvertedCdf),
+            Some(9.0)
+        );
+        assert_eq!(estimator.iqr(QuantileMethod::Linear), Some(4.5));
+    }
+
+    #[test]
+    fn harrell_davis_quantile_ok() {
+        let mut estimator = QnScaleEstimator::new(10);
+        assert_eq!(estimator.harrell_davis_quantile(0.5), None);
+
+        estimator.extend([9.0, -2.0, 4.0, 0.0, 1.0]);
+        let quantile = estimator.harrell_davis_quantile(0.5).unwrap();
+        assert!((quantile - 1.808_64).abs() < 1e-6, "quantile: {quantile}");
+        let quantile = estimator.trimmed_harrell_davis_quantile(0.5).unwrap();
+        assert!((quantile - 1.502_175).abs() < 1e-6, "quantile: {quantile}");
+    }
+
+    /// Compare against the naïve implementation.
+    #[test]
+    #[allow(clippy::cast_precision_loss)]
+    fn trimmed_and_winsorized_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let mut estimator = QnScaleEstimator::new(20).with_sorted_storage::<SumTree<_>>();
+        assert_eq!(estimator.trimmed_mean(0.1), None);
+        assert_eq!(estimator.winsorized_variance(0.1), None);
+
+        for _ in 0..100 {
+            estimator.push(f64::from(rng.i32(0..100)));
+            let mut samples: Vec<f64> = estimator.sorted.iter().collect();
+            let n = samples.len();
+            let n_trimmed = n / 5;
+
+            let trimmed = &samples[n_trimmed..n - n_trimmed];
+            let expected = trimmed.iter().sum::<f64>() / trimmed.len() as f64;
+            let mean = estimator.trimmed_mean(0.2).unwrap();
+            assert!((mean - expected).abs() < 1e-9, "{mean} vs {expected}");
+
+            let (lower, upper) = (samples[n_trimmed], samples[n - n_trimmed - 1]);
+            for sample in &mut samples {
+                *sample = sample.clamp(lower, upper);
+            }
+            let expected = samples.iter().sum::<f64>() / n as f64;
+            let mean = estimator.winsorized_mean(0.2).unwrap();
+            assert!((mean - expected).abs() < 1e-9, "{mean} vs {expected}");
+
+            if n >= 2 {
+                let expected = samples
+                    .iter()
+                    .map(|sample| (sample - expected).powi(2))
+                    .sum::<f64>()
+                    / (n - 1) as f64;
+                let variance = estimator.winsorized_variance(0.2).unwrap();
+                assert!(
+                    (variance - expected).abs() < 1e-6,
+                    "{variance} vs {expected}"
+                );
+            }
+        }
+    }
+
+    /// Large offset with the small noise should not lose the variance to the cancellation.
+    #[test]
+    fn winsorized_variance_offset_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let mut estimator = QnScaleEstimator::new(1000).with_sorted_storage::<SumTree<_>>();
+        estimator.extend((0..1000).map(|_| 1e9 + rng.f64()));
+
+        let mut samples: Vec<f64> = estimator.sorted.iter().collect();
+        let (lower, upper) = (samples[100], samples[899]);
+        for sample in &mut samples {
+            *sample = sample.clamp(lower, upper);
+        }
+        let mean = samples.iter().sum::<f64>() / 1000.0;
+        let expected = samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .sum::<f64>()
+            / 999.0;
+
+        let variance = estimator.winsorized_variance(0.1).unwrap();
+        assert!(
+            (variance / expected - 1.0).abs() < 1e-6,
+            "{variance} vs {expected}"
+        );
+    }
+
+    #[test]
+    fn iterative_scale_ok() {
+        let mut estimator = QnScaleEstimator::new(100);
+        assert_eq!(estimator.tau_scale(4.5, 3.0, 10), None);
+
+        // Outliers should not affect much:
+        estimator.extend((0..90).map(|i| f64::from(i % 10)));
+        estimator.extend([1000.0; 10]);
+
+        for estimate in [
+            estimator.biweight_midvariance(9.0, 100).unwrap(),
+            estimator.tau_scale(4.5, 3.0, 100).unwrap(),
+        ] {
+            assert_eq!(estimate.n_samples, 100);
+            assert!(estimate.converged, "{estimate:?}");
+            assert!((4.0..5.5).contains(&estimate.location), "{estimate:?}");
+            assert!((2.5..4.0).contains(&estimate.scale), "{estimate:?}");
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn implosion_fallback_ok() {
+        let samples = [1, 1, 1, 1, 1, 2, 2, 4];
+
+        let mut estimator = QnScaleEstimator::new(10);
+        estimator.extend(samples);
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.statistic, 0);
+        assert_eq!(scale.fallback, None);
+
+        let mut estimator = QnScaleEstimator::new(10)
+            .with_implosion_fallback(ImplosionFallback::NextNonZeroDifference);
+        estimator.extend(samples);
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.statistic, 1);
+        assert_eq!(
+            scale.fallback,
+            Some(ImplosionFallback::NextNonZeroDifference)
+        );
+
+        let mut estimator =
+            QnScaleEstimator::new(10).with_implosion_fallback(ImplosionFallback::Floor(0.5));
+        estimator.extend(samples.map(f64::from));
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.fallback, Some(ImplosionFallback::Floor(0.5)));
+        assert_eq!(
+            scale.applied_fallback(Normalization::FiniteSample),
+            Some(ImplosionFallback::Floor(0.5))
+        );
+        assert!((f64::from(scale) - 0.5).abs() < f64::EPSILON);
+
+        // The low floor does not bind on the non-imploded window:
+        let mut estimator =
+            QnScaleEstimator::new(10).with_implosion_fallback(ImplosionFallback::Floor(0.01));
+        estimator.extend([1.0, 2.0, 4.0, 8.0]);
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.applied_fallback(Normalization::FiniteSample), None);
+        assert!(f64::from(scale) > 1.0);
+
+        let mut estimator =
+            QnScaleEstimator::new(10).with_implosion_fallback(ImplosionFallback::Resolution(1.0));
+        estimator.extend(samples.map(f64::from));
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.statistic, 0.0);
+        assert_eq!(scale.fallback, Some(ImplosionFallback::Resolution(1.0)));
+        assert!((f64::from(scale) - 1.0 / 12.0_f64.sqrt()).abs() < f64::EPSILON);
+
+        // The resolution only replaces the imploded scale:
+        let mut estimator =
+            QnScaleEstimator::new(10).with_implosion_fallback(ImplosionFallback::Resolution(1.0));
+        estimator.extend([1.0, 2.0, 4.0]);
+        assert_eq!(estimator.estimate().unwrap().fallback, None);
+
+        // Nothing to fall back to:
+        let mut estimator = QnScaleEstimator::new(10)
+            .with_implosion_fallback(ImplosionFallback::NextNonZeroDifference);
+        estimator.extend([1, 1, 1]);
+        assert_eq!(estimator.estimate().unwrap().fallback, None);
+    }
 }
@@ -0,0 +1,362 @@
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    ops::{Add, Sub},
+};
+
+use crate::{
+    QnScaleEstimator,
+    RawMedian,
+    scale::ScaleEstimate,
+    sorted_vec::{SortedStorage, SortedVec},
+    window::{Landmark, LateSample},
+};
+
+/// Estimate of a window, which has been closed by [`HoppingWindows`].
+#[must_use]
+#[derive(Debug, Eq, PartialEq)]
+pub struct ClosedWindow<Ts, T> {
+    /// Start of the window (inclusive).
+    pub start: Ts,
+
+    /// End of the window (exclusive).
+    pub end: Ts,
+
+    /// `Qn` estimate of scale of the window samples.
+    pub scale: ScaleEstimate<T>,
+
+    /// Median of the window samples.
+    pub median: RawMedian<T>,
+}
+
+/// Driver, which emits one estimate per window instead of one per sample.
+///
+/// The windows are `[start, start + size)`, and each next window starts `hop` later
+/// than the previous one. Tumbling windows are the special case, where `hop` equals `size`.
+///
+/// A window closes as soon as a sample at or past its end arrives, so the samples only ever
+/// enter the oldest open window. When it closes, the samples preceding the next window
+/// get removed, and the overlapping part of the hopping windows is never rebuilt.
+/// A gap in the stream is skipped at once rather than window by window.
+///
+/// `Ts` is a timestamp type, and `D` is the type of `size` and `hop`, which could be added
+/// to a timestamp.
+#[must_use = "constructing a driver without using it makes no sense"]
+pub struct HoppingWindows<Ts, D, T, S = SortedVec<T>> {
+    /// Samples of the oldest open window, ordered by their timestamps.
+    items: VecDeque<(Ts, T)>,
+
+    /// Start of the oldest open window, unknown until the first sample
+    /// unless the origin is specified.
+    start: Option<Ts>,
+
+    /// End of the most recently closed window.
+    closed_end: Option<Ts>,
+
+    size: D,
+    hop: D,
+    estimator: QnScaleEstimator<T, Landmark, S>,
+}
+
+impl<Ts, D: Copy, T> HoppingWindows<Ts, D, T> {
+    /// Create a driver for the non-overlapping windows of the specified size.
+    pub const fn tumbling(size: D) -> Self {
+        Self::new(size, size)
+    }
+
+    /// Create a driver for the windows of the specified size, starting every `hop`.
+    ///
+    /// Normally, `hop` does not exceed `size`. Otherwise, the samples between the windows
+    /// are ignored.
+    pub const fn new(size: D, hop: D) -> Self {
+        Self {
+            items: VecDeque::new(),
+            start: None,
+            closed_end: None,
+            size,
+            hop,
+            estimator: QnScaleEstimator::landmark(),
+        }
+    }
+}
+
+impl<Ts, D, T, S> HoppingWindows<Ts, D, T, S> {
+    /// Align the windows to the specified origin.
+    ///
+    /// By default, the first window starts at the first sample's timestamp.
+    pub fn with_origin(mut self, origin: Ts) -> Self {
+        self.start = Some(origin);
+        self
+    }
+}
+
+impl<Ts, D, T, S> HoppingWindows<Ts, D, T, S>
+where
+    Ts: Copy + PartialOrd + Add<D, Output = Ts>,
+    D: Copy + Add<D, Output = D>,
+    T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>,
+    S: SortedStorage<T>,
+{
+    /// Push the upcoming timestamped value.
+    ///
+    /// Samples may arrive out of order, as long as their windows are still open.
+    /// When `hop` exceeds `size`, the samples between the windows are dropped.
+    ///
+    /// # Returns
+    ///
+    /// The windows closed by the sample's timestamp, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// [`LateSample`] if the sample precedes the end of the most recently closed window,
+    /// or the first window when none is closed yet.
+    pub fn push(
+        &mut self,
+        timestamp: Ts,
+        value: T,
+    ) -> Result<Vec<ClosedWindow<Ts, T>>, LateSample<Ts, T>> {
+        let start = *self.start.get_or_insert(timestamp);
+        if timestamp < start {
+            // Either the sample's windows are closed, or it falls into the gap before the oldest
+            // open window:
+            return if self.closed_end.is_some_and(|end| end <= timestamp) {
+                Ok(Vec::new())
+            } else {
+                Err(LateSample { timestamp, value })
+            };
+        }
+
+        let closed_windows = self.advance_to(timestamp);
+        if self.start.is_some_and(|start| start <= timestamp) && timestamp < self.end() {
+            let index = self.items.partition_point(|(other, _)| *other <= timestamp);
+            self.items.insert(index, (timestamp, value));
+            self.estimator.push(value);
+        }
+        Ok(closed_windows)
+    }
+
+    /// Close all the windows, which end at or before the specified timestamp.
+    ///
+    /// This is useful to emit the estimates when the stream goes quiet.
+    ///
+    /// # Returns
+    ///
+    /// The closed non-empty windows, oldest first.
+    pub fn advance_to(&mut self, timestamp: Ts) -> Vec<ClosedWindow<Ts, T>> {
+        let mut closed_windows = Vec::new();
+        while self.start.is_some() && self.end() <= timestamp {
+            if self.items.is_empty() {
+                self.skip_empty_windows(timestamp);
+                break;
+            }
+            closed_windows.extend(self.close_window());
+        }
+        closed_windows
+    }
+
+    /// Close all the open windows, for example, at the end of the stream.
+    ///
+    /// # Returns
+    ///
+    /// The closed non-empty windows, oldest first.
+    pub fn flush(&mut self) -> Vec<ClosedWindow<Ts, T>> {
+        let mut closed_windows = Vec::new();
+        while !self.items.is_empty() {
+            closed_windows.extend(self.close_window());
+        }
+        closed_windows
+    }
+
+    /// End of the oldest open window.
+    ///
+    /// # Panics
+    ///
+    /// The windows are not aligned yet.
+    fn end(&self) -> Ts {
+        self.start.expect("the windows should be aligned") + self.size
+    }
+
+    /// Close all the empty windows, which end at or before the timestamp, at once.
+    ///
+    /// This is a binary search over the number of the hops, so that a large gap in the stream
+    /// takes `O(log gap)` rather than a step per window.
+    fn skip_empty_windows(&mut self, timestamp: Ts) {
+        let mut start = self.start.expect("the windows should be aligned");
+        let (mut strides, mut stride) = (Vec::new(), self.hop);
+        while start + stride + self.size <= timestamp {
+            strides.push(stride);
+            stride = stride + stride;
+        }
+        // Now, find the start of the last window to close:
+        for stride in strides.into_iter().rev() {
+            if start + stride + self.size <= timestamp {
+                start = start + stride;
+            }
+        }
+        self.closed_end = Some(start + self.size);
+        self.start = Some(start + self.hop);
+    }
+
+    /// Emit the oldest open window, and move on to the next one.
+    fn close_window(&mut self) -> Option<ClosedWindow<Ts, T>> {
+        let start = self.start.expect("the windows should be aligned");
+        let end = start + self.size;
+        let closed_window =
+            self.estimator
+                .estimate()
+                .zip(self.estimator.median())
+                .map(|(scale, median)| ClosedWindow {
+                    start,
+                    end,
+                    scale,
+                    median,
+                });
+
+        self.closed_end = Some(end);
+        let start = start + self.hop;
+        self.start = Some(start);
+        while let Some((timestamp, value)) = self.items.front() {
+            if *timestamp >= start {
+                break;
+            }
+            self.estimator
+                .retract(value)
+                .expect("the value should also be present in the estimator");
+            self.items.pop_front();
+        }
+
+        closed_window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::ScaleKind;
+
+    #[test]
+    fn tumbling_ok() {
+        let mut windows = HoppingWindows::tumbling(10_u32);
+        assert_eq!(windows.push(0_u32, 1), Ok(vec![]));
+        assert_eq!(windows.push(5, 3), Ok(vec![]));
+        assert_eq!(windows.push(9, 2), Ok(vec![]));
+        assert_eq!(
+            windows.push(10, 100),
+            Ok(vec![ClosedWindow {
+                start: 0,
+                end: 10,
+                scale: ScaleEstimate {
+                    n_samples: 3,
+                    statistic: 1,
+                    kind: ScaleKind::Qn,
+                    fallback: None,
+                },
+                median: RawMedian::Odd(2),
+            }])
+        );
+
+        // The window [10, 20) closes, and [20, 30) gets skipped:
+        let closed_windows = windows.push(35, 1).unwrap();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].start, 10);
+        assert_eq!(closed_windows[0].median, RawMedian::Odd(100));
+
+        let closed_windows = windows.flush();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].start, 30);
+    }
+
+    #[test]
+    fn late_sample_ok() {
+        let mut windows = HoppingWindows::tumbling(10_u32).with_origin(100_u32);
+        assert!(windows.push(99, 1).is_err());
+        windows.push(111, 1).unwrap();
+        assert!(windows.push(105, 1).is_err());
+        assert_eq!(windows.push(110, 2), Ok(vec![]));
+        assert_eq!(
+            windows.flush()[0].median,
+            RawMedian::Even(1, 2),
+            "the out-of-order sample should be accounted for"
+        );
+    }
+
+    /// Compare against the freshly built estimators.
+    #[test]
+    fn hopping_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let samples: Vec<(u32, i32)> = (0..1000)
+            .map(|index| (index / 2, rng.i32(0..1000)))
+            .collect();
+
+        let mut windows = HoppingWindows::new(60, 10);
+        let mut closed_windows = Vec::new();
+        for (timestamp, value) in samples.iter().copied() {
+            closed_windows.extend(windows.push(timestamp, value).unwrap());
+        }
+        closed_windows.extend(windows.flush());
+        assert_eq!(closed_windows.len(), 50);
+
+        for closed_window in closed_windows {
+            let mut expected_estimator = QnScaleEstimator::landmark();
+            expected_estimator.extend(
+                samples
+                    .iter()
+                    .filter(|(timestamp, _)| {
+                        (closed_window.start..closed_window.end).contains(timestamp)
+                    })
+                    .map(|(_, value)| *value),
+            );
+            assert_eq!(Some(closed_window.scale), expected_estimator.estimate());
+            assert_eq!(Some(closed_window.median), expected_estimator.median());
+        }
+    }
+
+    /// A huge gap should be skipped at once.
+    #[test]
+    fn large_gap_ok() {
+        let mut windows = HoppingWindows::tumbling(10_u64);
+        windows.push(0_u64, 1).unwrap();
+        let closed_windows = windows.push(1_000_000_000_005, 2).unwrap();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].start, 0);
+        assert!(windows.push(1_000_000_000_000, 3).is_ok());
+        assert!(windows.push(999_999_999_999, 3).is_err());
+
+        let closed_windows = windows.flush();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].start, 1_000_000_000_000);
+        assert_eq!(closed_windows[0].scale.n_samples, 2);
+
+        // The gap between the hopping windows:
+        let mut windows = HoppingWindows::new(10_u64, 20).with_origin(0_u64);
+        assert!(windows.advance_to(1_000_000_000_015).is_empty());
+        assert_eq!(windows.push(1_000_000_000_017, 1), Ok(vec![]));
+        assert_eq!(windows.push(1_000_000_000_025, 2), Ok(vec![]));
+        let closed_windows = windows.flush();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].start, 1_000_000_000_020);
+    }
+
+    /// The samples between the windows should be dropped rather than reported late.
+    #[test]
+    fn gaps_ok() {
+        let mut windows = HoppingWindows::new(10_u32, 20).with_origin(0_u32);
+        assert_eq!(windows.push(1, 1), Ok(vec![]));
+        assert_eq!(windows.push(5, 2), Ok(vec![]));
+
+        let closed_windows = windows.push(15, 100).unwrap();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].scale.n_samples, 2);
+
+        assert_eq!(windows.push(17, 100), Ok(vec![]));
+        assert!(windows.push(9, 1).is_err());
+        assert_eq!(windows.push(25, 3), Ok(vec![]));
+        assert_eq!(windows.push(27, 4), Ok(vec![]));
+
+        let closed_windows = windows.flush();
+        assert_eq!(closed_windows.len(), 1);
+        assert_eq!(closed_windows[0].start, 20);
+        assert_eq!(closed_windows[0].scale.n_samples, 2);
+        assert_eq!(closed_windows[0].median, RawMedian::Even(3, 4));
+    }
+}
@@ -0,0 +1,266 @@
+use std::{
+    collections::{BTreeMap, HashMap, hash_map},
+    fmt::Debug,
+    hash::Hash,
+    ops::Sub,
+};
+
+use crate::{
+    QnScaleEstimator,
+    RawMedian,
+    scale::ScaleEstimate,
+    sorted_vec::{SortedStorage, SortedVec},
+    window::Window,
+};
+
+struct Entry<Ts, E> {
+    estimator: E,
+    last_seen: Ts,
+
+    /// Sequence number of the last access, which is the key in the LRU index.
+    last_access: u64,
+}
+
+/// Estimators keyed by, for example, host and endpoint.
+///
+/// An estimator gets created by the shared factory on the first use of its key.
+/// The keys, which have been idle for longer than the TTL, get evicted, and so do
+/// the least recently used keys beyond the maximum number of keys.
+///
+/// The idle eviction expects the access timestamps to be non-decreasing. When a clock
+/// goes backwards, the idle keys may be evicted later than due.
+#[must_use = "constructing a map without using it makes no sense"]
+pub struct KeyedEstimators<K, Ts, D, F, T, W = Window<T>, S = SortedVec<T>> {
+    entries: HashMap<K, Entry<Ts, QnScaleEstimator<T, W, S>>>,
+
+    /// Keys ordered by their last access, the least recently used first.
+    lru: BTreeMap<u64, K>,
+
+    n_accesses: u64,
+    ttl: Option<D>,
+    max_len: Option<usize>,
+    factory: F,
+}
+
+impl<K, Ts, D, F, T, W, S> KeyedEstimators<K, Ts, D, F, T, W, S>
+where
+    F: FnMut() -> QnScaleEstimator<T, W, S>,
+{
+    /// Create an empty map, which creates the estimators with the `factory`.
+    ///
+    /// By default, nothing gets evicted.
+    pub fn new(factory: F) -> Self {
+        Self {
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            n_accesses: 0,
+            ttl: None,
+            max_len: None,
+            factory,
+        }
+    }
+
+    /// Evict the keys, which have been idle for longer than `ttl`.
+    pub fn with_ttl(mut self, ttl: D) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Evict the least recently used keys beyond `max_len`.
+    ///
+    /// # Panics
+    ///
+    /// Zero `max_len`.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        assert_ne!(max_len, 0, "the maximum number of keys must be positive");
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Number of the keys.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no keys.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the keys.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.keys()
+    }
+}
+
+impl<K, Ts, D, F, T, W, S> KeyedEstimators<K, Ts, D, F, T, W, S>
+where
+    K: Clone + Eq + Hash,
+    Ts: Copy + PartialOrd + Sub<Ts, Output = D>,
+    D: PartialOrd,
+    F: FnMut() -> QnScaleEstimator<T, W, S>,
+{
+    /// Get the key's estimator, creating it if needed, and mark the key as used at `now`.
+    ///
+    /// This also evicts the idle keys, and the least recently used key
+    /// if the map is full.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn estimator(&mut self, key: K, now: Ts) -> &mut QnScaleEstimator<T, W, S> {
+        self.evict_idle(now);
+        if !self.entries.contains_key(&key)
+            && self
+                .max_len
+                .is_some_and(|max_len| self.entries.len() >= max_len)
+        {
+            let (_, evicted_key) = self
+                .lru
+                .pop_first()
+                .expect("the full map should not be empty");
+            self.entries.remove(&evicted_key);
+        }
+
+        self.n_accesses += 1;
+        let last_access = self.n_accesses;
+        let entry = match self.entries.entry(key) {
+            hash_map::Entry::Occupied(entry) => {
+                self.lru.remove(&entry.get().last_access);
+                self.lru.insert(last_access, entry.key().clone());
+                let entry = entry.into_mut();
+                entry.last_access = last_access;
+                entry.last_seen = now;
+                entry
+            }
+            hash_map::Entry::Vacant(entry) => {
+                self.lru.insert(last_access, entry.key().clone());
+                entry.insert(Entry {
+                    estimator: (self.factory)(),
+                    last_seen: now,
+                    last_access,
+                })
+            }
+        };
+        &mut entry.estimator
+    }
+
+    /// Evict the keys, which have been idle for longer than the TTL at `now`.
+    ///
+    /// This gets called automatically on each access, but it may also be called periodically
+    /// to release the memory when the keys go quiet.
+    pub fn evict_idle(&mut self, now: Ts) {
+        let Some(ttl) = &self.ttl else {
+            return;
+        };
+        while let Some(entry) = self.lru.values().next().map(|key| &self.entries[key]) {
+            // Only subtract the preceding timestamp, since a clock may go backwards:
+            if now <= entry.last_seen || now - entry.last_seen <= *ttl {
+                break;
+            }
+            if let Some((_, key)) = self.lru.pop_first() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+
+    /// Remove the key.
+    ///
+    /// # Returns
+    ///
+    /// The key's estimator, or [`None`] if there was no such key.
+    pub fn remove(&mut self, key: &K) -> Option<QnScaleEstimator<T, W, S>> {
+        let entry = self.entries.remove(key)?;
+        self.lru.remove(&entry.last_access);
+        Some(entry.estimator)
+    }
+}
+
+impl<K, Ts, D, F, T, W, S> KeyedEstimators<K, Ts, D, F, T, W, S>
+where
+    T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>,
+    S: SortedStorage<T>,
+{
+    /// Calculate the estimates of scale of all the non-empty estimators, in arbitrary order.
+    pub fn estimate_all(&self) -> impl Iterator<Item = (&K, ScaleEstimate<T>)> {
+        self.entries
+            .iter()
+            .filter_map(|(key, entry)| Some((key, entry.estimator.estimate()?)))
+    }
+
+    /// Obtain the medians of all the non-empty estimators, in arbitrary order.
+    pub fn median_all(&self) -> impl Iterator<Item = (&K, RawMedian<T>)> {
+        self.entries
+            .iter()
+            .filter_map(|(key, entry)| Some((key, entry.estimator.median()?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_all_ok() {
+        let mut estimators = KeyedEstimators::new(|| QnScaleEstimator::new(10));
+        estimators.estimator("a", 0_u32).extend([1, 2, 3]);
+        estimators.estimator("b", 0).extend([10, 20, 30]);
+        estimators.estimator("c", 0);
+
+        let mut estimates: Vec<_> = estimators
+            .estimate_all()
+            .map(|(key, scale)| (*key, scale.statistic))
+            .collect();
+        estimates.sort_unstable();
+        assert_eq!(estimates, [("a", 1), ("b", 10)]);
+
+        let mut medians: Vec<_> = estimators.median_all().collect();
+        medians.sort_unstable_by_key(|(key, _)| **key);
+        assert_eq!(
+            medians,
+            [(&"a", RawMedian::Odd(2)), (&"b", RawMedian::Odd(20))]
+        );
+    }
+
+    #[test]
+    fn ttl_ok() {
+        let mut estimators = KeyedEstimators::new(|| QnScaleEstimator::new(10)).with_ttl(10_u32);
+        estimators.estimator("a", 0_u32).push(1);
+        estimators.estimator("b", 5).push(1);
+        estimators.estimator("a", 10).push(2);
+
+        // `b` is idle for longer than the TTL:
+        estimators.evict_idle(16);
+        assert_eq!(estimators.keys().collect::<Vec<_>>(), [&"a"]);
+        assert_eq!(
+            estimators.estimator("a", 20).median(),
+            Some(RawMedian::Even(1, 2))
+        );
+    }
+
+    /// A clock going backwards should not underflow the unsigned timestamps.
+    #[test]
+    fn clock_backwards_ok() {
+        let mut estimators = KeyedEstimators::new(|| QnScaleEstimator::new(10)).with_ttl(10_u32);
+        estimators.estimator("a", 100_u32).push(1);
+        estimators.estimator("b", 5).push(1);
+        estimators.evict_idle(0);
+        assert_eq!(estimators.len(), 2);
+    }
+
+    #[test]
+    fn max_len_ok() {
+        let mut estimators = KeyedEstimators::new(|| QnScaleEstimator::new(10)).with_max_len(2);
+        estimators.estimator("a", 0_u32).push(1);
+        estimators.estimator("b", 1).push(1);
+        estimators.estimator("a", 2).push(2);
+
+        // `b` is the least recently used one:
+        estimators.estimator("c", 3).push(1);
+        let mut keys: Vec<_> = estimators.keys().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [&"a", &"c"]);
+
+        assert!(estimators.remove(&"a").is_some());
+        assert_eq!(estimators.len(), 1);
+    }
+}
@@ -1,13 +1,42 @@
 #![doc = include_str!("../README.md")]
 
+mod beta;
 mod dash_iter;
+mod decayed;
 mod estimator;
+mod hopping;
+mod keyed;
+mod m_scale;
 mod median;
+mod outlier;
 mod pick_list;
+mod quantile;
 mod rank;
+mod reference;
 mod scale;
 mod select;
+mod skip_list;
 mod sorted_vec;
+mod standardize;
+mod sum_tree;
+mod weighted;
 mod window;
 
-pub use crate::{estimator::QnScaleEstimator, median::RawMedian, scale::ScaleEstimate};
+pub use crate::{
+    decayed::DecayedQnScaleEstimator,
+    estimator::QnScaleEstimator,
+    hopping::{ClosedWindow, HoppingWindows},
+    keyed::KeyedEstimators,
+    m_scale::IterativeScaleEstimate,
+    median::RawMedian,
+    outlier::{Admission, Decision, OutlierDetector, Verdict},
+    quantile::QuantileMethod,
+    reference::{ReferenceDistribution, calibrate_qn},
+    scale::{ImplosionFallback, Normalization, ScaleEstimate, ScaleKind},
+    skip_list::SkipList,
+    sorted_vec::{SortedStorage, SortedVec},
+    standardize::{RobustStandardize, RobustStandardized},
+    sum_tree::{RangeMoments, SumTree},
+    weighted::WeightedQnScaleEstimator,
+    window::{ChronologicalStorage, Landmark, LateSample, TimedWindow, Window},
+};
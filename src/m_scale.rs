@@ -0,0 +1,207 @@
+use std::f64::consts::PI;
+
+use crate::scale::{normal_cdf, normal_quantile};
+
+/// Relative change of the estimates, below which the iterations stop.
+const TOLERANCE: f64 = 1e-9;
+
+/// Estimate of scale, which has been refined iteratively from the initial location and scale.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IterativeScaleEstimate {
+    /// Number of samples.
+    pub n_samples: usize,
+
+    /// Location, which the scale has been estimated around.
+    pub location: f64,
+
+    /// The actual scale, consistent with the standard deviation for normal distribution.
+    pub scale: f64,
+
+    /// Number of the performed iterations.
+    pub n_iterations: usize,
+
+    /// Whether the last iteration has changed the estimates by less than the tolerance.
+    ///
+    /// Otherwise, the iteration limit has been reached.
+    pub converged: bool,
+}
+
+/// Iterate the biweight location and midvariance.
+///
+/// The tuning constant `c` is expressed in the units of the raw median absolute deviation,
+/// and the seed scale is normal-consistent. Each iteration updates the location with the biweight
+/// location, and the scale with the square root of the biweight midvariance.
+pub fn biweight_midvariance(
+    samples: &[f64],
+    location: f64,
+    scale: f64,
+    c: f64,
+    max_iterations: usize,
+) -> IterativeScaleEstimate {
+    let mut estimate = IterativeScaleEstimate {
+        n_samples: samples.len(),
+        location,
+        scale,
+        n_iterations: 0,
+        converged: true,
+    };
+
+    // Zero scale is a fixed point, since all the deviations but the zero ones get rejected:
+    if scale == 0.0 {
+        return estimate;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let n = samples.len() as f64;
+    estimate.converged = false;
+    while !estimate.converged && estimate.n_iterations < max_iterations {
+        let IterativeScaleEstimate {
+            location, scale, ..
+        } = estimate;
+        let cutoff = c * scale * normal_quantile(0.75);
+
+        let (mut numerator, mut denominator) = (0.0, 0.0);
+        let (mut weighted_deviation, mut weight) = (0.0, 0.0);
+        for sample in samples {
+            let deviation = sample - location;
+            let u2 = (deviation / cutoff).powi(2);
+            if u2 < 1.0 {
+                let w = (1.0 - u2).powi(2);
+                numerator += (deviation * w).powi(2);
+                denominator += (1.0 - u2) * 5.0f64.mul_add(-u2, 1.0);
+                weighted_deviation = deviation.mul_add(w, weighted_deviation);
+                weight += w;
+            }
+        }
+
+        estimate.location = location + weighted_deviation / weight;
+        estimate.scale = (n * numerator).sqrt() / denominator.abs();
+        estimate.n_iterations += 1;
+        estimate.converged = (estimate.location - location).abs() <= TOLERANCE * scale
+            && (estimate.scale - scale).abs() <= TOLERANCE * scale;
+    }
+    estimate
+}
+
+/// Iterate the Yohai–Zamar τ-scale, as in `robustbase::scaleTau2`.
+///
+/// The tuning constants `c1` and `c2` are expressed in the units of the raw median absolute
+/// deviation, and the seed scale is normal-consistent. Each iteration updates the location
+/// with the weighted mean, and the scale with the truncated quadratic M-scale around it.
+pub fn tau_scale(
+    samples: &[f64],
+    location: f64,
+    scale: f64,
+    c1: f64,
+    c2: f64,
+    max_iterations: usize,
+) -> IterativeScaleEstimate {
+    let mut estimate = IterativeScaleEstimate {
+        n_samples: samples.len(),
+        location,
+        scale,
+        n_iterations: 0,
+        converged: true,
+    };
+    if scale == 0.0 {
+        return estimate;
+    }
+
+    // Expected truncated square of the standard normal deviation divided by the raw MAD:
+    let expected_rho = expected_truncated_square(c2 * normal_quantile(0.75));
+
+    #[allow(clippy::cast_precision_loss)]
+    let n = samples.len() as f64;
+    estimate.converged = false;
+    while !estimate.converged && estimate.n_iterations < max_iterations {
+        let IterativeScaleEstimate {
+            location, scale, ..
+        } = estimate;
+        let raw_scale = scale * normal_quantile(0.75);
+
+        let (mut weighted_sum, mut weight) = (0.0, 0.0);
+        for sample in samples {
+            let u2 = ((sample - location) / (c1 * raw_scale)).powi(2);
+            if u2 < 1.0 {
+                let w = (1.0 - u2).powi(2);
+                weighted_sum = sample.mul_add(w, weighted_sum);
+                weight += w;
+            }
+        }
+        let new_location = weighted_sum / weight;
+
+        let rho: f64 = samples
+            .iter()
+            .map(|sample| ((sample - new_location) / raw_scale).powi(2).min(c2 * c2))
+            .sum();
+
+        estimate.location = new_location;
+        estimate.scale = raw_scale * (rho / (n * expected_rho)).sqrt();
+        estimate.n_iterations += 1;
+        estimate.converged = (estimate.location - location).abs() <= TOLERANCE * scale
+            && (estimate.scale - scale).abs() <= TOLERANCE * scale;
+    }
+    estimate
+}
+
+/// `E[min(Z², b²)]` for the standard normal `Z`.
+fn expected_truncated_square(b: f64) -> f64 {
+    let density = (-b * b / 2.0).exp() / (2.0 * PI).sqrt();
+    let expected = b.mul_add(b, b.mul_add(-b, 1.0).mul_add(normal_cdf(b), -b * density));
+    2.0f64.mul_add(expected, -1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evenly spaced normal quantiles, which resemble a normal sample.
+    fn normal_sample(n: u32) -> Vec<f64> {
+        (1..=n)
+            .map(|i| normal_quantile((f64::from(i) - 0.5) / f64::from(n)))
+            .collect()
+    }
+
+    #[test]
+    fn expected_truncated_square_ok() {
+        assert!((expected_truncated_square(100.0) - 1.0).abs() < 1e-9);
+        assert!((expected_truncated_square(1.0) - 0.516_058_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn biweight_consistency_ok() {
+        let samples = normal_sample(1000);
+        let estimate = biweight_midvariance(&samples, 0.5, 2.0, 9.0, 100);
+        assert!(estimate.converged);
+        assert!(estimate.n_iterations > 1);
+        assert!(estimate.location.abs() < 1e-6, "{estimate:?}");
+        assert!((estimate.scale - 1.0).abs() < 0.02, "{estimate:?}");
+    }
+
+    #[test]
+    fn tau_consistency_ok() {
+        let samples = normal_sample(1000);
+        let estimate = tau_scale(&samples, 0.5, 2.0, 4.5, 3.0, 100);
+        assert!(estimate.converged);
+        assert!(estimate.n_iterations > 1);
+        assert!(estimate.location.abs() < 1e-6, "{estimate:?}");
+        assert!((estimate.scale - 1.0).abs() < 0.01, "{estimate:?}");
+    }
+
+    #[test]
+    fn iteration_limit_ok() {
+        let samples = normal_sample(100);
+        let estimate = tau_scale(&samples, 0.5, 2.0, 4.5, 3.0, 1);
+        assert!(!estimate.converged);
+        assert_eq!(estimate.n_iterations, 1);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn zero_scale_ok() {
+        let estimate = biweight_midvariance(&[1.0, 1.0, 2.0], 1.0, 0.0, 9.0, 100);
+        assert!(estimate.converged);
+        assert_eq!(estimate.n_iterations, 0);
+        assert_eq!(estimate.scale, 0.0);
+    }
+}
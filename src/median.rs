@@ -1,3 +1,4 @@
+#[cfg(feature = "num-traits")]
 use std::ops::{Add, Div};
 
 /// Intermediary median value which isn't converted to a single value yet.
@@ -25,17 +26,35 @@ where
     }
 }
 
+impl<T: Into<Self>> From<RawMedian<T>> for f64 {
+    /// Get the actual median, averaging the middle items for even-sized samples.
+    fn from(median: RawMedian<T>) -> Self {
+        match median {
+            RawMedian::Odd(median) => median.into(),
+            RawMedian::Even(left, right) => (left.into() + right.into()) / 2.0,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
+    #[cfg(feature = "num-traits")]
     fn odd_ok() {
         assert_eq!(RawMedian::Odd(42).to_median(), 42);
     }
 
     #[test]
+    #[cfg(feature = "num-traits")]
     fn even_ok() {
         assert_eq!(RawMedian::Even(42, 44).to_median(), 43);
     }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn into_f64_ok() {
+        assert_eq!(f64::from(RawMedian::Even(42, 43)), 42.5);
+    }
 }
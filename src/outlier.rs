@@ -0,0 +1,296 @@
+use std::{fmt::Debug, ops::Sub};
+
+use crate::{
+    QnScaleEstimator,
+    sorted_vec::{SortedStorage, SortedVec},
+    window::Window,
+};
+
+/// Whether the flagged values enter the window of [`OutlierDetector`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Admission {
+    /// All the values enter the window.
+    All,
+
+    /// Only the inliers enter the window, so the outliers never inflate the scale.
+    ///
+    /// Beware that a level shift makes all the subsequent values outliers.
+    InliersOnly,
+
+    /// Only the inliers enter the window, unless the specified number of the values
+    /// in a row have been flagged: then, the flagged values enter the window too,
+    /// so that the detector adapts to a level shift.
+    AfterConsecutive(usize),
+}
+
+/// Classification of a value by [`OutlierDetector`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Verdict {
+    Inlier,
+
+    /// The score is below the negated lower threshold.
+    Low,
+
+    /// The score is above the upper threshold.
+    High,
+}
+
+/// Decision of [`OutlierDetector`] on a value.
+#[must_use]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Decision {
+    pub verdict: Verdict,
+
+    /// Robust z-score, that is `(value - median) / scale`.
+    pub score: f64,
+
+    /// Window median, which the value has been compared against.
+    pub median: f64,
+
+    /// Normalized `Qn` scale of the window, which the value has been compared against.
+    pub scale: f64,
+
+    /// Whether the value has entered the window.
+    pub admitted: bool,
+}
+
+impl Decision {
+    /// Whether the value is flagged as an outlier.
+    #[must_use]
+    pub fn is_outlier(&self) -> bool {
+        self.verdict != Verdict::Inlier
+    }
+}
+
+/// Streaming outlier detector, as in Cafaro et al., which is based on the median and `Qn`.
+///
+/// Each value is scored against the window *before* the value gets inserted,
+/// so an outlier cannot mask itself.
+#[must_use = "constructing a detector without using it makes no sense"]
+pub struct OutlierDetector<T, S = SortedVec<T>> {
+    estimator: QnScaleEstimator<T, Window<T>, S>,
+    lower_threshold: f64,
+    upper_threshold: f64,
+    admission: Admission,
+    min_samples: usize,
+    n_consecutive_outliers: usize,
+}
+
+impl<T> OutlierDetector<T> {
+    /// Create a detector, which flags the values further than `threshold` scales away
+    /// from the median of the recent `window_len` values.
+    ///
+    /// # Panics
+    ///
+    /// Zero `window_len`, or non-positive `threshold`.
+    pub fn new(window_len: usize, threshold: f64) -> Self {
+        Self::from_estimator(QnScaleEstimator::new(window_len), threshold)
+    }
+}
+
+impl<T, S> OutlierDetector<T, S> {
+    /// Create a detector on top of the specified estimator, which may already contain samples.
+    ///
+    /// # Panics
+    ///
+    /// Non-positive `threshold`.
+    pub fn from_estimator(estimator: QnScaleEstimator<T, Window<T>, S>, threshold: f64) -> Self {
+        assert!(threshold > 0.0, "the threshold must be positive");
+        Self {
+            estimator,
+            lower_threshold: threshold,
+            upper_threshold: threshold,
+            admission: Admission::All,
+            min_samples: 2,
+            n_consecutive_outliers: 0,
+        }
+    }
+
+    /// Use the different thresholds for the values below and above the median.
+    ///
+    /// # Panics
+    ///
+    /// Non-positive thresholds.
+    pub fn with_thresholds(mut self, lower: f64, upper: f64) -> Self {
+        assert!(
+            lower > 0.0 && upper > 0.0,
+            "the thresholds must be positive"
+        );
+        self.lower_threshold = lower;
+        self.upper_threshold = upper;
+        self
+    }
+
+    /// Set the admission policy, by default all the values enter the window.
+    pub const fn with_admission(mut self, admission: Admission) -> Self {
+        self.admission = admission;
+        self
+    }
+
+    /// Do not classify the values until the window contains at least `min_samples` values.
+    /// Until then, all the values enter the window.
+    ///
+    /// By default, classification starts from the third value, since the scale of a single
+    /// sample is always zero.
+    pub const fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Underlying estimator.
+    pub const fn estimator(&self) -> &QnScaleEstimator<T, Window<T>, S> {
+        &self.estimator
+    }
+}
+
+impl<T, S> OutlierDetector<T, S>
+where
+    T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T> + Into<f64>,
+    S: SortedStorage<T>,
+{
+    /// Classify the value against the current window, and then insert it,
+    /// according to the admission policy.
+    ///
+    /// # Returns
+    ///
+    /// The decision, or [`None`] if the window is still warming up, or its scale is zero.
+    /// In both cases, the value cannot be classified and enters the window.
+    pub fn push(&mut self, value: T) -> Option<Decision> {
+        let decision = self.classify(value);
+        let admitted = decision.is_none_or(|decision| decision.admitted);
+        if admitted {
+            self.estimator.push(value);
+        }
+        decision
+    }
+
+    /// Classify the value against the current window, and decide on its admission.
+    fn classify(&mut self, value: T) -> Option<Decision> {
+        if self.estimator.n_samples() < self.min_samples.max(1) {
+            return None;
+        }
+        let (median, scale) = self.estimator.seed()?;
+        if scale <= 0.0 {
+            return None;
+        }
+
+        let score = (value.into() - median) / scale;
+
+        let verdict = if score < -self.lower_threshold {
+            Verdict::Low
+        } else if score > self.upper_threshold {
+            Verdict::High
+        } else {
+            Verdict::Inlier
+        };
+        let admitted = if verdict == Verdict::Inlier {
+            self.n_consecutive_outliers = 0;
+            true
+        } else {
+            self.n_consecutive_outliers += 1;
+            match self.admission {
+                Admission::All => true,
+                Admission::InliersOnly => false,
+                Admission::AfterConsecutive(n) => self.n_consecutive_outliers > n,
+            }
+        };
+
+        Some(Decision {
+            verdict,
+            score,
+            median,
+            scale,
+            admitted,
+        })
+    }
+}
+
+/// Calculate `(value - median) / scale`, which is zero for the value equal to the median,
+/// and infinite for the other values when the scale is zero.
+pub fn robust_score(value: f64, median: f64, scale: f64) -> f64 {
+    let deviation = value - median;
+    if scale > 0.0 {
+        deviation / scale
+    } else if deviation == 0.0 {
+        0.0
+    } else {
+        f64::INFINITY.copysign(deviation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warm_up_ok() {
+        let mut detector = OutlierDetector::new(10, 3.0).with_min_samples(3);
+        assert_eq!(detector.push(1.0), None);
+        assert_eq!(detector.push(2.0), None);
+        assert_eq!(detector.push(3.0), None);
+        assert!(detector.push(100.0).unwrap().is_outlier());
+    }
+
+    #[test]
+    fn decision_ok() {
+        let mut detector = OutlierDetector::new(10, 3.0).with_thresholds(10.0, 3.0);
+        for value in [1, 2, 3, 4, 5] {
+            detector.push(value);
+        }
+        let scale: f64 = detector.estimator().estimate().unwrap().into();
+
+        let decision = detector.push(-10).unwrap();
+        assert_eq!(decision.verdict, Verdict::Inlier);
+        assert!((decision.median - 3.0).abs() < 1e-9);
+        assert!((decision.scale - scale).abs() < 1e-9);
+        assert!((decision.score + 13.0 / scale).abs() < 1e-9);
+
+        let decision = detector.push(100).unwrap();
+        assert_eq!(decision.verdict, Verdict::High);
+        assert!(decision.admitted);
+    }
+
+    #[test]
+    fn zero_scale_ok() {
+        let mut detector = OutlierDetector::new(10, 3.0).with_min_samples(1);
+        detector.push(1);
+        assert_eq!(detector.push(1), None);
+        assert_eq!(detector.push(0), None);
+        assert_eq!(detector.estimator().n_samples(), 3);
+    }
+
+    /// The detector should not lock onto the first samples.
+    #[test]
+    fn inliers_only_warm_up_ok() {
+        let mut detector = OutlierDetector::new(10, 3.0).with_admission(Admission::InliersOnly);
+        for value in [1, 1, 2, 3, 4, 5] {
+            assert!(
+                detector
+                    .push(value)
+                    .is_none_or(|decision| decision.admitted)
+            );
+        }
+        assert_eq!(detector.estimator().n_samples(), 6);
+    }
+
+    #[test]
+    fn admission_ok() {
+        let mut detector =
+            OutlierDetector::new(10, 3.0).with_admission(Admission::AfterConsecutive(2));
+        detector.estimator.extend([1, 2, 3, 2, 1]);
+
+        let decisions: Vec<bool> = [100, 100, 100, 100]
+            .into_iter()
+            .map(|value| detector.push(value).unwrap().admitted)
+            .collect();
+        assert_eq!(decisions, [false, false, true, true]);
+        assert_eq!(detector.estimator().n_samples(), 7);
+
+        let mut detector = OutlierDetector::new(10, 3.0).with_admission(Admission::InliersOnly);
+        detector.estimator.extend([1, 2, 3, 2, 1]);
+        for _ in 0..10 {
+            assert!(!detector.push(100).unwrap().admitted);
+        }
+        assert_eq!(detector.estimator().n_samples(), 5);
+    }
+}
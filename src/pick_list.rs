@@ -1,4 +1,4 @@
-use std::ops::Sub;
+use std::ops::{Mul, Sub};
 
 /// Build the list denoted by `L` in the papers
 /// (the function itself is also known as `pickL()`).
@@ -11,30 +11,62 @@ use std::ops::Sub;
 ///   there should be a smart algorithm to select the _k_-th largest in a sorted `X + (-X)` matrix
 ///   in near-linear time. I spent some time digging into papers and decided to _maybe_ address it later.
 ///   At least, I'm allocating the buffer just once and use it for the entire [`crate::select::select`] call.
-pub fn pick_list<V, I>(window: I, min: V, max: V, into_buffer: &mut Vec<V>)
-where
-    I: Clone + Iterator<Item = V>,
-    V: Copy + PartialOrd + Sub<V, Output = V>,
+pub fn pick_list<V, I, J, C>(
+    rows: I,
+    columns: J,
+    combine: C,
+    min: V,
+    max: V,
+    into_buffer: &mut Vec<V>,
+) where
+    I: Iterator<Item = V>,
+    J: Clone + Iterator<Item = V>,
+    V: Copy + PartialOrd,
+    C: Copy + Fn(V, V) -> V,
 {
     into_buffer.clear();
 
     // This is tracking our maximum pointer.
-    let mut max_column_iter = window.clone().peekable();
+    let mut max_column_iter = columns.peekable();
 
-    for lhs in window {
+    for lhs in rows {
         // Update the maximum pointer: move right until a strictly smaller element is found:
-        while max_column_iter.next_if(|rhs| lhs - *rhs >= max).is_some() {}
+        while max_column_iter
+            .next_if(|rhs| combine(lhs, *rhs) >= max)
+            .is_some()
+        {}
 
         // Okay, now we're holding at our first actual element (you still holding your beer? 🍺)
         // Let's clone the iterator and push everything till the specified minimum.
         let min_column_iter = max_column_iter
             .clone()
-            .map(|rhs| lhs - rhs)
+            .map(|rhs| combine(lhs, rhs))
             .take_while(|rhs| *rhs > min);
         into_buffer.extend(min_column_iter);
     }
 }
 
+/// Weighted counterpart of [`pick_list`]: collect the elements in the columns `left[i]..right[i]`
+/// of each row `i` along with their weights `w_i * w_j`.
+pub fn weighted_pick_list<V, W>(
+    window: &[(V, W)],
+    left: &[usize],
+    right: &[usize],
+    into_buffer: &mut Vec<(V, W)>,
+) where
+    V: Copy + Sub<V, Output = V>,
+    W: Copy + Mul<W, Output = W>,
+{
+    into_buffer.clear();
+    for ((lhs, row_weight), (left, right)) in window.iter().zip(left.iter().zip(right)) {
+        into_buffer.extend(
+            window[*left..*right]
+                .iter()
+                .map(|(rhs, column_weight)| (*lhs - *rhs, *row_weight * *column_weight)),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -51,13 +83,13 @@ mod tests {
         let window = [1, 2, 3].into_iter();
         let mut buffer = Vec::new();
 
-        pick_list(window.clone(), -1, 1, &mut buffer);
+        pick_list(window.clone(), window.clone(), Sub::sub, -1, 1, &mut buffer);
         assert_eq!(buffer, [0, 0, 0]);
 
-        pick_list(window.clone(), -2, 2, &mut buffer);
+        pick_list(window.clone(), window.clone(), Sub::sub, -2, 2, &mut buffer);
         assert_eq!(buffer, [0, -1, 1, 0, -1, 1, 0]);
 
-        pick_list(window, -3, 3, &mut buffer);
+        pick_list(window.clone(), window, Sub::sub, -3, 3, &mut buffer);
         assert_eq!(buffer, [0, -1, -2, 1, 0, -1, 2, 1, 0]);
     }
 
@@ -66,11 +98,34 @@ mod tests {
         let mut buffer = Vec::new();
 
         // Zero matrix:
-        pick_list([1, 1].into_iter(), -1, 1, &mut buffer);
+        pick_list(
+            [1, 1].into_iter(),
+            [1, 1].into_iter(),
+            Sub::sub,
+            -1,
+            1,
+            &mut buffer,
+        );
         assert_eq!(buffer, [0, 0, 0, 0]);
 
         // Zero matrix, corner case:
-        pick_list([1, 1].into_iter(), 0, 0, &mut buffer);
-        assert_eq!(buffer, []);
+        pick_list(
+            [1, 1].into_iter(),
+            [1, 1].into_iter(),
+            Sub::sub,
+            0,
+            0,
+            &mut buffer,
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn weighted_matrix_3x3_ok() {
+        let window = [(1, 1), (2, 2), (3, 3)];
+        let mut buffer = Vec::new();
+
+        weighted_pick_list(&window, &[1, 0, 2], &[3, 1, 2], &mut buffer);
+        assert_eq!(buffer, [(-1, 2), (-2, 3), (1, 2)]);
     }
 }
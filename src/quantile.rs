@@ -0,0 +1,269 @@
+use crate::beta::Beta;
+
+/// Sample quantile definition, as classified by Hyndman and Fan[^1].
+///
+/// The variants are named after `numpy.quantile` methods, and the types are those of R's `quantile()`.
+///
+/// [^1]: [Hyndman, R. J. and Fan, Y. (1996). Sample quantiles in statistical packages](https://doi.org/10.2307/2684934).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum QuantileMethod {
+    /// Type 1: inverse of the empirical distribution function.
+    InvertedCdf,
+
+    /// Type 2: same as type 1, but averages at the discontinuities.
+    AveragedInvertedCdf,
+
+    /// Type 3: nearest even order statistic.
+    ClosestObservation,
+
+    /// Type 4: linear interpolation of the empirical distribution function.
+    InterpolatedInvertedCdf,
+
+    /// Type 5: piecewise linear function, where the knots are the midpoints of the steps
+    /// of the empirical distribution function.
+    Hazen,
+
+    /// Type 6: `p[k] = E[F(x[k])]`, used by Minitab and SPSS.
+    Weibull,
+
+    /// Type 7: `p[k] = mode[F(x[k])]`, the default of R and `NumPy`.
+    #[default]
+    Linear,
+
+    /// Type 8: `x[k]` is approximately median-unbiased regardless of the distribution,
+    /// recommended by Hyndman and Fan.
+    MedianUnbiased,
+
+    /// Type 9: `x[k]` is approximately unbiased for the expected order statistics
+    /// of the normal distribution.
+    NormalUnbiased,
+}
+
+impl QuantileMethod {
+    /// Type number in Hyndman and Fan's classification.
+    #[must_use]
+    pub const fn hyndman_fan_type(self) -> u8 {
+        match self {
+            Self::InvertedCdf => 1,
+            Self::AveragedInvertedCdf => 2,
+            Self::ClosestObservation => 3,
+            Self::InterpolatedInvertedCdf => 4,
+            Self::Hazen => 5,
+            Self::Weibull => 6,
+            Self::Linear => 7,
+            Self::MedianUnbiased => 8,
+            Self::NormalUnbiased => 9,
+        }
+    }
+}
+
+/// Calculate the `p`-quantile of the `n` sorted samples, where `get` returns the `index`-th
+/// smallest sample (starting from `0`).
+///
+/// This follows R's implementation, including its fuzz for the floating-point errors.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn quantile(get: impl Fn(usize) -> f64, n: usize, p: f64, method: QuantileMethod) -> f64 {
+    const FUZZ: f64 = 4.0 * f64::EPSILON;
+
+    // Order statistic by the 1-based index, clamped to the sample:
+    let order_statistic = |index: f64| get((index as usize).clamp(1, n) - 1);
+
+    let n_float = n as f64;
+    match method {
+        QuantileMethod::InvertedCdf
+        | QuantileMethod::AveragedInvertedCdf
+        | QuantileMethod::ClosestObservation => {
+            let np = if method == QuantileMethod::ClosestObservation {
+                n_float.mul_add(p, -0.5)
+            } else {
+                n_float * p
+            };
+            let j = (np + FUZZ).floor();
+            #[allow(clippy::float_cmp)]
+            let gamma = match method {
+                QuantileMethod::InvertedCdf => f64::from(u8::from(np > j)),
+                QuantileMethod::AveragedInvertedCdf => f64::from(u8::from(np > j) + 1) / 2.0,
+                _ => f64::from(u8::from(np != j || j % 2.0 == 1.0)),
+            };
+            interpolate(order_statistic(j), order_statistic(j + 1.0), gamma)
+        }
+        _ => {
+            let (alpha, beta) = match method {
+                QuantileMethod::InterpolatedInvertedCdf => (0.0, 1.0),
+                QuantileMethod::Hazen => (0.5, 0.5),
+                QuantileMethod::Weibull => (0.0, 0.0),
+                QuantileMethod::Linear => (1.0, 1.0),
+                QuantileMethod::MedianUnbiased => (1.0 / 3.0, 1.0 / 3.0),
+                _ => (3.0 / 8.0, 3.0 / 8.0),
+            };
+            let np = p.mul_add(n_float + 1.0 - alpha - beta, alpha);
+            let j = (np + FUZZ).floor();
+            let gamma = if (np - j).abs() < FUZZ { 0.0 } else { np - j };
+            interpolate(order_statistic(j), order_statistic(j + 1.0), gamma)
+        }
+    }
+}
+
+/// Calculate the Harrell–Davis `p`-quantile[^1] of the `n` sorted samples, where `get` returns
+/// the `index`-th smallest sample (starting from `0`).
+///
+/// This is the weighted sum of all the order statistics, the weights are given by
+/// `Beta(p (n + 1), (1 - p) (n + 1))`. In the trimmed version[^2], the weights are
+/// restricted to the highest density interval of width `1 / sqrt(n)`, so only `O(sqrt(n))`
+/// order statistics are involved.
+///
+/// [^1]: [Harrell, F. E. and Davis, C. E. (1982). A new distribution-free quantile estimator](https://doi.org/10.1093/biomet/69.3.635).
+/// [^2]: [Akinshin, A. (2022). Trimmed Harrell-Davis quantile estimator based on the highest density interval of the given width](https://doi.org/10.48550/arXiv.2111.11776).
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::float_cmp
+)]
+pub fn harrell_davis_quantile(get: impl Fn(usize) -> f64, n: usize, p: f64, trimmed: bool) -> f64 {
+    if n == 1 || p == 0.0 {
+        return get(0);
+    }
+    if p == 1.0 {
+        return get(n - 1);
+    }
+
+    let n_float = n as f64;
+    let beta = Beta {
+        a: p * (n_float + 1.0),
+        b: (1.0 - p) * (n_float + 1.0),
+    };
+    let (left, right) = if trimmed {
+        beta.highest_density_interval(1.0 / n_float.sqrt())
+    } else {
+        (0.0, 1.0)
+    };
+    let (left_cdf, right_cdf) = (beta.cdf(left), beta.cdf(right));
+    let cdf = |index: usize| {
+        let x = (index as f64 / n_float).clamp(left, right);
+        (beta.cdf(x) - left_cdf) / (right_cdf - left_cdf)
+    };
+
+    // Only the order statistics within the interval have non-zero weights:
+    let start = ((left * n_float).floor() as usize).min(n - 1);
+    let end = ((right * n_float).ceil() as usize).clamp(start + 1, n);
+    let mut previous_cdf = cdf(start);
+    (start..end).fold(0.0, |sum, index| {
+        let next_cdf = cdf(index + 1);
+        let weight = next_cdf - previous_cdf;
+        previous_cdf = next_cdf;
+        if weight == 0.0 {
+            sum
+        } else {
+            get(index).mul_add(weight, sum)
+        }
+    })
+}
+
+/// Interpolate between the adjacent order statistics, not touching the upper one
+/// when it has no weight (it may be infinite).
+#[allow(clippy::float_cmp)]
+fn interpolate(lower: f64, upper: f64, gamma: f64) -> f64 {
+    if gamma == 0.0 {
+        lower
+    } else if gamma == 1.0 {
+        upper
+    } else {
+        (upper - lower).mul_add(gamma, lower)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLES: [f64; 10] = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+
+    const METHODS: [QuantileMethod; 9] = [
+        QuantileMethod::InvertedCdf,
+        QuantileMethod::AveragedInvertedCdf,
+        QuantileMethod::ClosestObservation,
+        QuantileMethod::InterpolatedInvertedCdf,
+        QuantileMethod::Hazen,
+        QuantileMethod::Weibull,
+        QuantileMethod::Linear,
+        QuantileMethod::MedianUnbiased,
+        QuantileMethod::NormalUnbiased,
+    ];
+
+    /// `quantile(1:10, 0.25, type = 1:9)` in R.
+    #[test]
+    fn r_ok() {
+        let expected = [3.0, 3.0, 2.0, 2.5, 3.0, 2.75, 3.25, 2.916_666_7, 2.9375];
+        for (method, expected) in METHODS.into_iter().zip(expected) {
+            let quantile = quantile(|index| SAMPLES[index], 10, 0.25, method);
+            assert!((quantile - expected).abs() < 1e-6, "{method:?}: {quantile}");
+        }
+    }
+
+    /// `quantile(1:10, 0.5, type = 1:3)` in R.
+    #[test]
+    fn discontinuous_median_ok() {
+        let expected = [5.0, 5.5, 5.0];
+        for (method, expected) in METHODS.into_iter().zip(expected) {
+            let quantile = quantile(|index| SAMPLES[index], 10, 0.5, method);
+            assert!((quantile - expected).abs() < 1e-6, "{method:?}: {quantile}");
+        }
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn extremes_ok() {
+        for method in METHODS {
+            assert_eq!(
+                quantile(|index| SAMPLES[index], 7, 0.0, method),
+                1.0,
+                "{method:?}"
+            );
+            assert_eq!(
+                quantile(|index| SAMPLES[index], 7, 1.0, method),
+                7.0,
+                "{method:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn harrell_davis_symmetric_ok() {
+        for trimmed in [false, true] {
+            for n in 1..=10 {
+                let quantile = harrell_davis_quantile(|index| SAMPLES[index], n, 0.5, trimmed);
+                let expected = (SAMPLES[0] + SAMPLES[n - 1]) / 2.0;
+                assert!(
+                    (quantile - expected).abs() < 1e-9,
+                    "n: {n}, trimmed: {trimmed}, quantile: {quantile}"
+                );
+            }
+        }
+    }
+
+    /// Reference values are obtained by numerical integration of the Beta density.
+    #[test]
+    fn harrell_davis_ok() {
+        let samples = [-2.0, 0.0, 1.0, 4.0, 9.0];
+        for (p, trimmed, expected) in [
+            (0.25, false, -0.605_360_8),
+            (0.25, true, -0.958_937_9),
+            (0.5, false, 1.808_64),
+            (0.5, true, 1.502_174_9),
+            (0.75, false, 5.661_950_5),
+            (0.75, true, 6.363_614_8),
+        ] {
+            let quantile =
+                harrell_davis_quantile(|index| samples[index], samples.len(), p, trimmed);
+            assert!(
+                (quantile - expected).abs() < 1e-6,
+                "p: {p}, trimmed: {trimmed}, quantile: {quantile}"
+            );
+        }
+    }
+}
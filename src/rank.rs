@@ -12,63 +12,135 @@
 //!   Rows are «sorted» in descending order, and columns are «sorted» in ascending order
 //!   (consider pairwise sums of the window's elements and the same window's negated elements).
 //!
+//! - The matrix is defined by its rows, its columns, and the function combining them.
+//!   `X + (-X)` is the window subtracted from itself. `X + X` is the window added
+//!   to the reversed window, which keeps the rows and columns sorted the same way.
+//!
 //! - The reference implementation raises [the concern][1], so I'm using the definition
 //!   rather than blindly re-writing the reference implementation.
 //!
 //! [1]: https://github.com/cafaro/FQN/issues/1
 
-use std::ops::Sub;
+use std::ops::{Add, Mul, Sub};
 
 /// Calculate `rank+(A, a)` from the original papers, that is number of elements greater than `guard`.
 ///
 /// Also known as `rank+` in the original papers, and `rankRightV` in the reference implementation.
 #[must_use]
-pub fn n_greater<V, I>(window: I, guard: V) -> usize
+pub fn n_greater<V, I, J, C>(rows: I, columns: J, combine: C, guard: V) -> usize
 where
-    V: Copy + PartialOrd<V> + Sub<V, Output = V>,
-    I: Clone + ExactSizeIterator<Item = V>,
+    V: Copy + PartialOrd<V>,
+    I: Iterator<Item = V>,
+    J: ExactSizeIterator<Item = V>,
+    C: Fn(V, V) -> V,
 {
-    let window_size = window.len();
-    let mut column_iter = window.clone().enumerate().peekable();
-
-    window
-        .map(|lhs| {
-            // Move right until a smaller element is found:
-            while column_iter.next_if(|(_, rhs)| lhs - *rhs > guard).is_some() {}
-
-            // Count the elements on the left, excluding the current one
-            // (which is no longer less than the guard):
-            column_iter
-                .peek()
-                .map_or(window_size, |(column_index, _)| *column_index)
-        })
-        .sum()
+    let n_columns = columns.len();
+    let mut column_iter = columns.enumerate().peekable();
+
+    rows.map(|lhs| {
+        // Move right until a smaller element is found:
+        while column_iter
+            .next_if(|(_, rhs)| combine(lhs, *rhs) > guard)
+            .is_some()
+        {}
+
+        // Count the elements on the left, excluding the current one
+        // (which is no longer less than the guard):
+        column_iter
+            .peek()
+            .map_or(n_columns, |(column_index, _)| *column_index)
+    })
+    .sum()
 }
 
 /// Calculate `rank-(A, a)`, that is number of elements less than `guard`.
 #[must_use]
-pub fn n_smaller<V, I>(window: I, guard: V) -> usize
+pub fn n_smaller<V, I, J, C>(rows: I, columns: J, combine: C, guard: V) -> usize
+where
+    V: Copy + PartialOrd<V>,
+    I: Iterator<Item = V>,
+    J: ExactSizeIterator<Item = V>,
+    C: Fn(V, V) -> V,
+{
+    let n_columns = columns.len();
+    let mut column_iter = columns.enumerate().peekable();
+
+    rows.map(|lhs| {
+        // Note that here I use `>=`, it will stop at the first strictly smaller element,
+        // and it must be counted.
+        while column_iter
+            .next_if(|(_, rhs)| combine(lhs, *rhs) >= guard)
+            .is_some()
+        {}
+
+        column_iter
+            .peek()
+            .map_or(0, |(column_index, _)| n_columns - *column_index)
+    })
+    .sum()
+}
+
+/// Weighted [`n_greater`]: total weight of the elements greater than `guard`,
+/// where the matrix element `(i, j)` weighs `w_i * w_j`.
+///
+/// Also stores the number of such elements for each row into `boundaries`
+/// (these are the leftmost elements of each row).
+#[must_use]
+pub fn weighted_n_greater<V, W>(window: &[(V, W)], guard: V, boundaries: &mut [usize]) -> W
+where
+    V: Copy + PartialOrd<V> + Sub<V, Output = V>,
+    W: Copy + Default + Add<W, Output = W> + Mul<W, Output = W>,
+{
+    debug_assert_eq!(window.len(), boundaries.len());
+
+    let mut column_index = 0;
+    let mut column_weight = W::default(); // total weight of the columns on the left
+    let mut total_weight = W::default();
+
+    for ((lhs, row_weight), boundary) in window.iter().zip(boundaries) {
+        // Move right until a not-greater element is found:
+        while column_index < window.len() && *lhs - window[column_index].0 > guard {
+            column_weight = column_weight + window[column_index].1;
+            column_index += 1;
+        }
+        *boundary = column_index;
+        total_weight = total_weight + *row_weight * column_weight;
+    }
+
+    total_weight
+}
+
+/// Weighted [`n_smaller`]: total weight of the elements less than `guard`,
+/// where the matrix element `(i, j)` weighs `w_i * w_j`.
+///
+/// Also stores the index of the first such element for each row into `boundaries`
+/// (these are the rightmost elements of each row).
+#[must_use]
+pub fn weighted_n_smaller<V, W>(window: &[(V, W)], guard: V, boundaries: &mut [usize]) -> W
 where
     V: Copy + PartialOrd<V> + Sub<V, Output = V>,
-    I: Clone + ExactSizeIterator<Item = V>,
+    W: Copy + Default + Add<W, Output = W> + Sub<W, Output = W> + Mul<W, Output = W>,
 {
-    let window_size = window.len();
-    let mut column_iter = window.clone().enumerate().peekable();
-
-    window
-        .map(|lhs| {
-            // Note that here I use `>=`, it will stop at the first strictly smaller element,
-            // and it must be counted.
-            while column_iter
-                .next_if(|(_, rhs)| lhs - *rhs >= guard)
-                .is_some()
-            {}
-
-            column_iter
-                .peek()
-                .map_or(0, |(column_index, _)| window_size - *column_index)
-        })
-        .sum()
+    debug_assert_eq!(window.len(), boundaries.len());
+
+    let window_weight = window
+        .iter()
+        .fold(W::default(), |sum, (_, weight)| sum + *weight);
+    let mut column_index = 0;
+    let mut column_weight = W::default(); // total weight of the columns on the left
+    let mut total_weight = W::default();
+
+    for ((lhs, row_weight), boundary) in window.iter().zip(boundaries) {
+        // Move right until a strictly smaller element is found:
+        while column_index < window.len() && *lhs - window[column_index].0 >= guard {
+            column_weight = column_weight + window[column_index].1;
+            column_index += 1;
+        }
+        *boundary = column_index;
+        total_weight = total_weight + *row_weight * (window_weight - column_weight);
+    }
+
+    total_weight
 }
 
 #[cfg(test)]
@@ -85,15 +157,15 @@ mod tests {
     fn matrix_2x2_ok() {
         let window = [1, 2].into_iter();
 
-        assert_eq!(n_greater(window.clone(), -2), 4);
-        assert_eq!(n_greater(window.clone(), -1), 3);
-        assert_eq!(n_greater(window.clone(), 0), 1);
-        assert_eq!(n_greater(window.clone(), 1), 0);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -2), 4);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -1), 3);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 0), 1);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 1), 0);
 
-        assert_eq!(n_smaller(window.clone(), 2), 4);
-        assert_eq!(n_smaller(window.clone(), 1), 3);
-        assert_eq!(n_smaller(window.clone(), 0), 1);
-        assert_eq!(n_smaller(window, -1), 0);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 2), 4);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 1), 3);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 0), 1);
+        assert_eq!(n_smaller(window.clone(), window, Sub::sub, -1), 0);
     }
 
     /// # Matrix
@@ -107,15 +179,15 @@ mod tests {
     fn matrix_3x3_with_repetitions_ok() {
         let window = [1, 2, 2].into_iter();
 
-        assert_eq!(n_greater(window.clone(), -2), 9);
-        assert_eq!(n_greater(window.clone(), -1), 7);
-        assert_eq!(n_greater(window.clone(), 0), 2);
-        assert_eq!(n_greater(window.clone(), 1), 0);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -2), 9);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -1), 7);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 0), 2);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 1), 0);
 
-        assert_eq!(n_smaller(window.clone(), 2), 9);
-        assert_eq!(n_smaller(window.clone(), 1), 7);
-        assert_eq!(n_smaller(window.clone(), 0), 2);
-        assert_eq!(n_smaller(window, -1), 0);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 2), 9);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 1), 7);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 0), 2);
+        assert_eq!(n_smaller(window.clone(), window, Sub::sub, -1), 0);
     }
 
     /// # Matrix
@@ -129,18 +201,56 @@ mod tests {
     fn matrix_3x3_ok() {
         let window = [1, 2, 3].into_iter();
 
-        assert_eq!(n_greater(window.clone(), -3), 9);
-        assert_eq!(n_greater(window.clone(), -2), 8);
-        assert_eq!(n_greater(window.clone(), -1), 6);
-        assert_eq!(n_greater(window.clone(), 0), 3);
-        assert_eq!(n_greater(window.clone(), 1), 1);
-        assert_eq!(n_greater(window.clone(), 2), 0);
-
-        assert_eq!(n_smaller(window.clone(), 3), 9);
-        assert_eq!(n_smaller(window.clone(), 2), 8);
-        assert_eq!(n_smaller(window.clone(), 1), 6);
-        assert_eq!(n_smaller(window.clone(), 0), 3);
-        assert_eq!(n_smaller(window.clone(), -1), 1);
-        assert_eq!(n_smaller(window, -2), 0);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -3), 9);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -2), 8);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, -1), 6);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 0), 3);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 1), 1);
+        assert_eq!(n_greater(window.clone(), window.clone(), Sub::sub, 2), 0);
+
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 3), 9);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 2), 8);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 1), 6);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, 0), 3);
+        assert_eq!(n_smaller(window.clone(), window.clone(), Sub::sub, -1), 1);
+        assert_eq!(n_smaller(window.clone(), window, Sub::sub, -2), 0);
+    }
+
+    /// # Matrix
+    ///
+    /// ```text
+    /// 0, -1, -1
+    /// 1,  0,  0
+    /// 1,  0,  0
+    /// ```
+    ///
+    /// # Weights
+    ///
+    /// ```text
+    /// 1, 3
+    /// 3, 9
+    /// ```
+    #[test]
+    fn weighted_matrix_3x3_ok() {
+        let window = [(1, 1), (2, 3)];
+        let mut boundaries = [0; 2];
+
+        assert_eq!(weighted_n_greater(&window, -2, &mut boundaries), 16);
+        assert_eq!(boundaries, [2, 2]);
+        assert_eq!(weighted_n_greater(&window, -1, &mut boundaries), 13);
+        assert_eq!(boundaries, [1, 2]);
+        assert_eq!(weighted_n_greater(&window, 0, &mut boundaries), 3);
+        assert_eq!(boundaries, [0, 1]);
+        assert_eq!(weighted_n_greater(&window, 1, &mut boundaries), 0);
+        assert_eq!(boundaries, [0, 0]);
+
+        assert_eq!(weighted_n_smaller(&window, 2, &mut boundaries), 16);
+        assert_eq!(boundaries, [0, 0]);
+        assert_eq!(weighted_n_smaller(&window, 1, &mut boundaries), 13);
+        assert_eq!(boundaries, [0, 1]);
+        assert_eq!(weighted_n_smaller(&window, 0, &mut boundaries), 3);
+        assert_eq!(boundaries, [1, 2]);
+        assert_eq!(weighted_n_smaller(&window, -1, &mut boundaries), 0);
+        assert_eq!(boundaries, [2, 2]);
     }
 }
@@ -0,0 +1,329 @@
+use std::f64::consts::PI;
+
+use crate::{
+    QnScaleEstimator,
+    scale::{Normalization, ScaleEstimate, ScaleKind, normal_cdf, normal_quantile},
+};
+
+/// Distribution, for which scale parameter the estimate should be consistent.
+///
+/// See [`ScaleEstimate::normalize_for`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ReferenceDistribution {
+    /// Normal distribution, the scale parameter is the standard deviation.
+    #[default]
+    Normal,
+
+    /// Laplace distribution, the scale parameter is `b` in `exp(-|x - μ| / b) / 2b`.
+    Laplace,
+
+    /// Cauchy distribution, the scale parameter is the half width at half maximum.
+    Cauchy,
+
+    /// Uniform distribution, the scale parameter is the width of the support.
+    Uniform,
+
+    /// Logistic distribution, the scale parameter is `s` in `1 / (1 + exp(-(x - μ) / s))`.
+    Logistic,
+}
+
+impl ReferenceDistribution {
+    /// Distribution function of the centered distribution with the unit scale.
+    #[must_use]
+    pub fn cdf(self, x: f64) -> f64 {
+        match self {
+            Self::Normal => normal_cdf(x),
+            Self::Laplace if x < 0.0 => x.exp() / 2.0,
+            Self::Laplace => 1.0 - (-x).exp() / 2.0,
+            Self::Cauchy => x.atan() / PI + 0.5,
+            Self::Uniform => (x + 0.5).clamp(0.0, 1.0),
+            Self::Logistic => 1.0 / (1.0 + (-x).exp()),
+        }
+    }
+
+    /// Inverse of [`ReferenceDistribution::cdf`].
+    #[must_use]
+    pub fn quantile(self, p: f64) -> f64 {
+        match self {
+            Self::Normal => normal_quantile(p),
+            Self::Laplace if p < 0.5 => (2.0 * p).ln(),
+            Self::Laplace => -(2.0 * (1.0 - p)).ln(),
+            Self::Cauchy => (PI * (p - 0.5)).tan(),
+            Self::Uniform => p - 0.5,
+            Self::Logistic => (p / (1.0 - p)).ln(),
+        }
+    }
+
+    /// Distribution function of the absolute difference `|X_1 - X_2|` of two independent samples.
+    #[must_use]
+    pub fn difference_cdf(self, x: f64) -> f64 {
+        if x <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            Self::Normal => 2.0f64.mul_add(normal_cdf(x / 2.0f64.sqrt()), -1.0),
+            Self::Laplace => 1.0 - (-x).exp() * (2.0 + x) / 2.0,
+            Self::Cauchy => 2.0 * (x / 2.0).atan() / PI,
+            Self::Uniform => {
+                let complement = 1.0 - x.min(1.0);
+                complement.mul_add(-complement, 1.0)
+            }
+            Self::Logistic => {
+                // Distribution function of `X_1 - X_2` is `e^x (e^x - 1 - x) / (e^x - 1)²`:
+                let exp_m1 = x.exp_m1();
+                2.0f64.mul_add((exp_m1 + 1.0) * (exp_m1 - x) / (exp_m1 * exp_m1), -1.0)
+            }
+        }
+    }
+
+    /// Inverse of [`ReferenceDistribution::difference_cdf`].
+    ///
+    /// # Panics
+    ///
+    /// `p` is not within `[0, 1)`.
+    #[must_use]
+    pub fn difference_quantile(self, p: f64) -> f64 {
+        assert!(
+            (0.0..1.0).contains(&p),
+            "the probability must be within [0, 1)"
+        );
+        match self {
+            Self::Normal => 2.0f64.sqrt() * normal_quantile((1.0 + p) / 2.0),
+            Self::Cauchy => 2.0 * (PI * p / 2.0).tan(),
+            Self::Uniform => 1.0 - (1.0 - p).sqrt(),
+            Self::Laplace | Self::Logistic => bisect(|x| self.difference_cdf(x), p),
+        }
+    }
+
+    /// Asymptotic constant, which makes the estimate of the specified kind consistent
+    /// for the scale parameter.
+    ///
+    /// # Panics
+    ///
+    /// [`ScaleKind::GeneralizedQn`] with `h` not within `1..=n_samples`.
+    #[must_use]
+    pub fn consistency_constant(self, kind: ScaleKind, n_samples: usize) -> f64 {
+        match kind {
+            ScaleKind::Qn => 1.0 / self.difference_quantile(0.25),
+            ScaleKind::GeneralizedQn { h } => {
+                assert!(
+                    (1..=n_samples).contains(&h),
+                    "h must be within 1..=n_samples"
+                );
+                #[allow(clippy::cast_precision_loss)]
+                let p = ((h - 1) as f64 / n_samples as f64).powi(2);
+                1.0 / self.difference_quantile(p)
+            }
+            ScaleKind::Shamos => 1.0 / self.difference_quantile(0.5),
+            ScaleKind::Mad => 1.0 / self.quantile(0.75),
+            ScaleKind::Sn => {
+                // The inner median `med_j |x - x_j|` grows with `|x|` for the symmetric
+                // unimodal distributions, so the outer median is the one at the upper quartile:
+                let x = self.quantile(0.75);
+                1.0 / bisect(|y| self.cdf(x + y) - self.cdf(x - y), 0.5)
+            }
+        }
+    }
+}
+
+impl<T: Into<f64>> ScaleEstimate<T> {
+    /// Calculate the actual scale estimate, which is consistent for the scale parameter
+    /// of the reference distribution.
+    ///
+    /// For [`ReferenceDistribution::Normal`], this is the same as
+    /// [`Normalization::FiniteSample`]. Other distributions use the asymptotic constants only,
+    /// see [`calibrate_qn`] for the finite-sample ones.
+    #[must_use]
+    pub fn normalize_for(self, distribution: ReferenceDistribution) -> f64 {
+        if distribution == ReferenceDistribution::Normal {
+            return self.normalize(Normalization::FiniteSample);
+        }
+        let constant = distribution.consistency_constant(self.kind, self.n_samples);
+        self.normalize_with(constant)
+    }
+}
+
+/// Calculate the finite-sample `Qn` constant by Monte Carlo simulation.
+///
+/// The `sample` function should draw from the reference distribution with the unit scale.
+/// Each of the `n_replications` fills the window of `window_len` samples, and the constant
+/// is the reciprocal of the mean raw statistic. Passing the constant
+/// to [`ScaleEstimate::normalize_with`] gives the scale estimate, which is unbiased
+/// for the same window length.
+///
+/// # Panics
+///
+/// `window_len` is less than `2`, since the single sample has the zero statistic,
+/// or zero `n_replications`.
+#[must_use]
+pub fn calibrate_qn(
+    window_len: usize,
+    n_replications: usize,
+    mut sample: impl FnMut() -> f64,
+) -> f64 {
+    assert!(
+        window_len >= 2,
+        "the window should contain at least two samples"
+    );
+    assert!(
+        n_replications != 0,
+        "the number of replications should be positive"
+    );
+
+    let mut estimator = QnScaleEstimator::new(window_len);
+    let mut sum = 0.0;
+    for _ in 0..n_replications {
+        estimator.clear();
+        estimator.extend((0..window_len).map(|_| sample()));
+        sum += estimator
+            .estimate()
+            .expect("the window should not be empty")
+            .statistic;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let n_replications = n_replications as f64;
+    n_replications / sum
+}
+
+/// Solve `f(x) = target` for the non-decreasing `f`, such that `f(0) <= target`.
+///
+/// If `f` does not reach the target below `2^64`, the solution is considered infinite.
+fn bisect(f: impl Fn(f64) -> f64, target: f64) -> f64 {
+    const MAX_EXPANSIONS: usize = 64;
+
+    let (mut lower, mut upper) = (0.0, 1.0);
+    // Expand the bracket until it contains the solution:
+    for _ in 0..MAX_EXPANSIONS {
+        if f(upper) >= target {
+            break;
+        }
+        lower = upper;
+        upper *= 2.0;
+    }
+    if f(upper) < target {
+        return f64::INFINITY;
+    }
+    for _ in 0..100 {
+        let middle = (lower + upper) / 2.0;
+        if f(middle) < target {
+            lower = middle;
+        } else {
+            upper = middle;
+        }
+    }
+    (lower + upper) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+
+    const DISTRIBUTIONS: [ReferenceDistribution; 5] = [
+        ReferenceDistribution::Normal,
+        ReferenceDistribution::Laplace,
+        ReferenceDistribution::Cauchy,
+        ReferenceDistribution::Uniform,
+        ReferenceDistribution::Logistic,
+    ];
+
+    #[test]
+    fn inverse_ok() {
+        for distribution in DISTRIBUTIONS {
+            for p in [0.1, 0.25, 0.5, 0.9] {
+                let x = distribution.quantile(p);
+                assert!((distribution.cdf(x) - p).abs() < 1e-8, "{distribution:?}");
+
+                let x = distribution.difference_quantile(p);
+                assert!(
+                    (distribution.difference_cdf(x) - p).abs() < 1e-8,
+                    "{distribution:?}"
+                );
+            }
+        }
+    }
+
+    /// The normal constants should match the conventional ones.
+    #[test]
+    fn normal_ok() {
+        let distribution = ReferenceDistribution::Normal;
+        for (kind, expected) in [
+            (ScaleKind::Qn, 2.219_144),
+            (ScaleKind::Sn, 1.1926),
+            (ScaleKind::Mad, 1.4826),
+            (ScaleKind::Shamos, 1.048_358),
+        ] {
+            let constant = distribution.consistency_constant(kind, 100);
+            assert!((constant - expected).abs() < 1e-4, "{kind:?}: {constant}");
+        }
+    }
+
+    #[test]
+    fn qn_ok() {
+        for (distribution, expected) in [
+            (ReferenceDistribution::Laplace, 1.930_503),
+            (ReferenceDistribution::Cauchy, 1.207_107),
+            (ReferenceDistribution::Uniform, 7.464_102),
+            (ReferenceDistribution::Logistic, 1.307_883),
+        ] {
+            let constant = distribution.consistency_constant(ScaleKind::Qn, 100);
+            assert!(
+                (constant - expected).abs() < 1e-6,
+                "{distribution:?}: {constant}"
+            );
+        }
+    }
+
+    #[test]
+    fn bisect_unreachable_ok() {
+        assert!(bisect(|_| 0.0, 1.0).is_infinite());
+        assert!(bisect(|x| x, f64::INFINITY).is_infinite());
+    }
+
+    #[test]
+    #[should_panic = "h must be within 1..=n_samples"]
+    fn consistency_constant_zero_samples_panics() {
+        let _ = ReferenceDistribution::Laplace
+            .consistency_constant(ScaleKind::GeneralizedQn { h: 0 }, 0);
+    }
+
+    #[test]
+    fn normalize_for_ok() {
+        let estimate = ScaleEstimate {
+            n_samples: 10,
+            statistic: 1.0,
+            kind: ScaleKind::Mad,
+            fallback: None,
+        };
+        let scale = estimate.normalize_for(ReferenceDistribution::Laplace);
+        assert!((scale - 1.0 / 2.0f64.ln()).abs() < 1e-9, "scale: {scale}");
+    }
+
+    #[test]
+    fn calibrate_qn_ok() {
+        let mut rng = Rng::with_seed(42);
+        let constant = calibrate_qn(10, 20_000, || normal_quantile(rng.f64()));
+        let expected = ScaleEstimate {
+            n_samples: 10,
+            statistic: 1.0,
+            kind: ScaleKind::Qn,
+            fallback: None,
+        }
+        .normalize(Normalization::FiniteSample);
+        assert!(
+            (constant / expected - 1.0).abs() < 0.01,
+            "constant: {constant}, expected: {expected}"
+        );
+
+        let scale = ScaleEstimate::new(10, 2.0, ScaleKind::Qn).normalize_with(constant);
+        assert!((scale / constant - 2.0).abs() < 1e-12, "scale: {scale}");
+    }
+
+    #[test]
+    #[should_panic = "the window should contain at least two samples"]
+    fn calibrate_qn_single_sample_panics() {
+        let _ = calibrate_qn(1, 1, || 0.0);
+    }
+}
@@ -1,5 +1,87 @@
-/// Calculated Qn estimate of scale.
+use std::f64::consts::SQRT_2;
+
+const QN_ASYMPTOTIC_CONSTANT: f64 = 2.219_144_465_985_08;
+const SN_ASYMPTOTIC_CONSTANT: f64 = 1.1926;
+const MAD_ASYMPTOTIC_CONSTANT: f64 = 1.4826;
+const SHAMOS_ASYMPTOTIC_CONSTANT: f64 = 1.048_358;
+
+/// Finite-sample `Qn` factors for `n = 2..=100`, obtained by Monte Carlo simulation
+/// of the normal samples, see `simulate_qn_factors` in the tests.
+///
+/// The relative standard error is about `3e-4`.
+#[allow(clippy::approx_constant)]
+const QN_FACTORS: [f64; 99] = [
+    0.3993, 0.9937, 0.5131, 0.8439, 0.6122, 0.8588, 0.6697, 0.8737, 0.7202, 0.8892, 0.7571, 0.9025,
+    0.7853, 0.9130, 0.8078, 0.9213, 0.8260, 0.9276, 0.8409, 0.9336, 0.8531, 0.9386, 0.8645, 0.9431,
+    0.8738, 0.9470, 0.8815, 0.9502, 0.8890, 0.9530, 0.8956, 0.9557, 0.9013, 0.9578, 0.9063, 0.9601,
+    0.9105, 0.9619, 0.9148, 0.9638, 0.9185, 0.9655, 0.9219, 0.9667, 0.9255, 0.9679, 0.9278, 0.9693,
+    0.9308, 0.9701, 0.9331, 0.9712, 0.9356, 0.9725, 0.9378, 0.9727, 0.9398, 0.9745, 0.9420, 0.9748,
+    0.9437, 0.9759, 0.9453, 0.9766, 0.9470, 0.9772, 0.9485, 0.9778, 0.9497, 0.9787, 0.9514, 0.9791,
+    0.9522, 0.9797, 0.9536, 0.9799, 0.9543, 0.9802, 0.9559, 0.9811, 0.9568, 0.9813, 0.9576, 0.9818,
+    0.9583, 0.9821, 0.9598, 0.9828, 0.9603, 0.9833, 0.9614, 0.9831, 0.9623, 0.9834, 0.9628, 0.9838,
+    0.9641, 0.9842, 0.9646,
+];
+
+/// Finite-sample `Sn` factors for `n = 2..=100`, obtained by Monte Carlo simulation
+/// of the normal samples, see `simulate_sn_factors` in the tests.
+///
+/// The relative standard error is below `4e-4`.
+const SN_FACTORS: [f64; 99] = [
+    0.7431, 1.8490, 0.9548, 1.3485, 0.9939, 1.1986, 1.0047, 1.1321, 1.0072, 1.0960, 1.0064, 1.0745,
+    1.0049, 1.0604, 1.0038, 1.0505, 1.0030, 1.0424, 1.0018, 1.0369, 1.0009, 1.0326, 1.0011, 1.0297,
+    1.0006, 1.0272, 1.0001, 1.0247, 1.0001, 1.0226, 1.0003, 1.0211, 1.0002, 1.0194, 1.0001, 1.0183,
+    0.9998, 1.0173, 0.9996, 1.0164, 0.9995, 1.0158, 0.9995, 1.0146, 0.9999, 1.0137, 0.9992, 1.0135,
+    0.9995, 1.0124, 0.9995, 1.0121, 0.9994, 1.0117, 0.9997, 1.0108, 0.9995, 1.0111, 0.9997, 1.0101,
+    0.9998, 1.0103, 0.9996, 1.0101, 0.9998, 1.0096, 0.9997, 1.0093, 0.9996, 1.0093, 0.9999, 1.0090,
+    0.9996, 1.0089, 0.9998, 1.0084, 0.9991, 1.0079, 0.9999, 1.0082, 0.9997, 1.0075, 0.9994, 1.0076,
+    0.9994, 1.0073, 1.0000, 1.0074, 0.9994, 1.0073, 0.9997, 1.0068, 0.9999, 1.0066, 0.9998, 1.0067,
+    1.0004, 1.0064, 1.0001,
+];
+
+/// Finite-sample generalized `Qn` factors for `n = 5..=11` and `h = n / 2 + 2..n`, obtained
+/// by Monte Carlo simulation of the normal samples, see `simulate_generalized_qn_factors`
+/// in the tests.
+///
+/// They correct the bias, which remains after the quantile of `|X_1 - X_2|` is taken
+/// at the actual fraction of the pairs.
+const GENERALIZED_QN_FACTORS: [&[f64]; 7] = [
+    &[1.0508],
+    &[1.0299],
+    &[0.9829, 1.0272],
+    &[0.9857, 1.0222],
+    &[0.9772, 0.9863, 1.0213],
+    &[0.9779, 0.9899, 1.0202],
+    &[0.9752, 0.9794, 0.9923, 1.0198],
+];
+
+/// Estimator, which has produced a [`ScaleEstimate`].
+///
+/// It defines the normalization constant, which makes the estimate consistent
+/// with the standard deviation for normal distribution.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScaleKind {
+    /// Rousseeuw–Croux `Qn`.
+    Qn,
+
+    /// Generalized `Qn`, that is the `h(h-1)/2`-th smallest pairwise difference,
+    /// where `h` is not necessarily `floor(n / 2) + 1`.
+    GeneralizedQn { h: usize },
+
+    /// Rousseeuw–Croux `Sn`.
+    Sn,
+
+    /// Median absolute deviation.
+    Mad,
+
+    /// Shamos estimator, that is the median of the pairwise absolute differences.
+    Shamos,
+}
+
+/// Calculated estimate of scale.
+///
+/// More fields may be added in the future, so use [`ScaleEstimate::new`] to construct it.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
 pub struct ScaleEstimate<T> {
     /// Number of samples.
     pub n_samples: usize,
@@ -9,27 +91,720 @@ pub struct ScaleEstimate<T> {
     /// It needs to be multiplied by the scaling coefficients before it may be considered
     /// an «actual scale». See the implementations for concrete `T`.
     pub statistic: T,
+
+    /// Estimator, which has produced the statistic.
+    pub kind: ScaleKind,
+
+    /// Implosion fallback, which the normalization takes into account, if any.
+    ///
+    /// [`ImplosionFallback::Floor`] is recorded whenever it is configured, since only
+    /// the normalization tells whether it binds, see [`ScaleEstimate::applied_fallback`].
+    pub fallback: Option<ImplosionFallback<T>>,
+}
+
+impl<T> ScaleEstimate<T> {
+    /// Wrap the raw statistic of the specified kind.
+    #[must_use]
+    pub const fn new(n_samples: usize, statistic: T, kind: ScaleKind) -> Self {
+        Self {
+            n_samples,
+            statistic,
+            kind,
+            fallback: None,
+        }
+    }
+}
+
+/// Way to avoid the zero scale on heavily tied data.
+///
+/// See [`crate::QnScaleEstimator::with_implosion_fallback`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ImplosionFallback<T> {
+    /// The smallest non-zero pairwise difference, that is the next non-zero quantile
+    /// of the pairwise differences after the `Qn` one.
+    ///
+    /// It gets normalized just like the original statistic.
+    NextNonZeroDifference,
+
+    /// Resolution of the data, for example, the sensor quantization step.
+    ///
+    /// The imploded scale is replaced with the standard deviation of the rounding error,
+    /// that is `resolution / sqrt(12)`.
+    Resolution(T),
+
+    /// Minimum scale.
+    ///
+    /// Any smaller scale, imploded or not, is clamped to it.
+    Floor(T),
+}
+
+impl<T: Into<f64>> ImplosionFallback<T> {
+    /// Apply the fallback to the normalized scale.
+    fn apply(self, scale: f64) -> f64 {
+        match self {
+            Self::Resolution(resolution) if scale == 0.0 => resolution.into() / 12.0_f64.sqrt(),
+            Self::Floor(floor) => scale.max(floor.into()),
+            Self::NextNonZeroDifference | Self::Resolution(_) => scale,
+        }
+    }
+}
+
+/// How the statistic gets turned into the actual scale, see [`ScaleEstimate::normalize`].
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Normalization {
+    /// The statistic as is.
+    Raw,
+
+    /// Asymptotic consistency constant only, which is biased for the small samples.
+    Asymptotic,
+
+    /// Asymptotic consistency constant along with the finite-sample bias correction.
+    #[default]
+    FiniteSample,
+}
+
+impl<T: Into<f64>> ScaleEstimate<T> {
+    /// Calculate the actual scale estimate, that is the statistic multiplied by
+    /// the normalization constant.
+    ///
+    /// Converting the estimate into [`f64`] is the same as [`Normalization::FiniteSample`].
+    /// The [`ImplosionFallback::Resolution`] and [`ImplosionFallback::Floor`] fallbacks
+    /// apply to the normalized scale, but not to the raw statistic.
+    #[must_use]
+    pub fn normalize(self, normalization: Normalization) -> f64 {
+        let constant = match normalization {
+            Normalization::Raw => return self.statistic.into(),
+            Normalization::Asymptotic => self.asymptotic_normalization_constant(),
+            Normalization::FiniteSample => self.normalization_constant(),
+        };
+        self.normalize_with(constant)
+    }
+
+    /// Calculate the actual scale estimate with the custom normalization constant,
+    /// for example, the one from [`crate::calibrate_qn`].
+    ///
+    /// The fallbacks apply just like in [`ScaleEstimate::normalize`].
+    #[must_use]
+    pub fn normalize_with(self, constant: f64) -> f64 {
+        let scale = constant * self.statistic.into();
+        self.fallback
+            .map_or(scale, |fallback| fallback.apply(scale))
+    }
+}
+
+impl<T: Copy + Into<f64>> ScaleEstimate<T> {
+    /// Fallback, which has actually changed the estimate with the specified normalization,
+    /// if any.
+    ///
+    /// [`ImplosionFallback::NextNonZeroDifference`] has always replaced the statistic,
+    /// while the other fallbacks only apply to the normalized scale: for example,
+    /// the floor binds only when the scale would otherwise be below it.
+    #[must_use]
+    pub fn applied_fallback(&self, normalization: Normalization) -> Option<ImplosionFallback<T>> {
+        let fallback = self.fallback?;
+        let scale = Self {
+            fallback: None,
+            ..*self
+        }
+        .normalize(normalization);
+        let is_applied = match fallback {
+            ImplosionFallback::NextNonZeroDifference => true,
+            _ if normalization == Normalization::Raw => false,
+            ImplosionFallback::Resolution(_) => scale == 0.0,
+            ImplosionFallback::Floor(floor) => scale < floor.into(),
+        };
+        is_applied.then_some(fallback)
+    }
 }
 
-impl<T: TryInto<f64>> ScaleEstimate<T> {
+impl<T> ScaleEstimate<T> {
+    fn asymptotic_normalization_constant(&self) -> f64 {
+        match self.kind {
+            ScaleKind::Qn => QN_ASYMPTOTIC_CONSTANT,
+            ScaleKind::GeneralizedQn { h } => {
+                // The fraction of the pairs tends to the squared fraction of the samples:
+                #[allow(clippy::cast_precision_loss)]
+                let p = ((h - 1) as f64 / self.n_samples as f64).powi(2);
+                generalized_qn_constant(p)
+            }
+            ScaleKind::Sn => SN_ASYMPTOTIC_CONSTANT,
+            ScaleKind::Mad => MAD_ASYMPTOTIC_CONSTANT,
+            ScaleKind::Shamos => SHAMOS_ASYMPTOTIC_CONSTANT,
+        }
+    }
+
     fn normalization_constant(&self) -> f64 {
+        match self.kind {
+            ScaleKind::Qn => self.qn_normalization_constant(),
+            ScaleKind::GeneralizedQn { h } => self.generalized_qn_normalization_constant(h),
+            ScaleKind::Sn => self.sn_normalization_constant(),
+            ScaleKind::Mad => self.mad_normalization_constant(),
+            ScaleKind::Shamos => self.shamos_normalization_constant(),
+        }
+    }
+
+    /// The small-sample factors are simulated, and the larger samples use the approximation
+    /// from the paper of Akinshin.
+    fn qn_normalization_constant(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let n = self.n_samples as f64;
+
+        let dn = match self.n_samples {
+            0 | 1 => 1.0,
+            n_samples @ 2..=100 => QN_FACTORS[n_samples - 2],
+            n_samples if n_samples & 1 == 1 => 1.0 - 1.594 / n + 3.22 / n.powi(2),
+            _ => 1.0 - 3.672 / n + 11.087 / n.powi(2),
+        };
+
+        QN_ASYMPTOTIC_CONSTANT * dn
+    }
+
+    /// The constant makes the `h(h-1)/2`-th smallest pairwise difference of the normal sample
+    /// consistent, that is the reciprocal of the respective quantile of `|X_1 - X_2|`.
+    ///
+    /// This falls back to the original `Qn` constants when `h` is the original one. Otherwise,
+    /// the quantile is taken at the actual fraction of the pairs, and the remaining bias
+    /// is corrected by the simulated factors for `n <= 11`, and by their approximation
+    /// for the larger samples.
+    fn generalized_qn_normalization_constant(&self, h: usize) -> f64 {
+        let n = self.n_samples;
+        if h == n / 2 + 1 {
+            return self.qn_normalization_constant();
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        let p = (h * (h - 1)) as f64 / (n * (n - 1)) as f64;
+        let dn = n
+            .checked_sub(5)
+            .and_then(|row| GENERALIZED_QN_FACTORS.get(row))
+            .zip(h.checked_sub(n / 2 + 2))
+            .and_then(|(factors, column)| factors.get(column))
+            .copied()
+            .unwrap_or_else(|| {
+                #[allow(clippy::cast_precision_loss)]
+                let (n, n_excluded) = (n as f64, n.saturating_sub(h).max(1) as f64);
+                1.0 + p.mul_add(p.mul_add(1.7942, -1.0775), -0.3271) / n
+                    + 0.1746 / (n_excluded * n)
+                    + 1.0402 / n.powi(2)
+            });
+
+        generalized_qn_constant(p) * dn
+    }
+
+    /// The finite-sample factors are obtained by Monte Carlo simulation of the normal samples,
+    /// the same way Akinshin has done for his table, and for `n > 100` they are approximated
+    /// separately for the odd and even `n`.
+    fn sn_normalization_constant(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let n = self.n_samples as f64;
+
+        let cn = match self.n_samples {
+            0 | 1 => 1.0,
+            n_samples @ 2..=100 => SN_FACTORS[n_samples - 2],
+            n_samples if n_samples & 1 == 1 => 1.0 + 0.6585 / n - 1.0817 / n.powi(2),
+            _ => 1.0 - 0.0126 / n - 0.7127 / n.powi(2),
+        };
+
+        SN_ASYMPTOTIC_CONSTANT * cn
+    }
+
+    /// The small-sample factors are taken from the paper of Croux and Rousseeuw
+    /// on time-efficient algorithms for robust estimators of scale.
+    fn mad_normalization_constant(&self) -> f64 {
+        #[allow(clippy::cast_precision_loss)]
+        let n = self.n_samples as f64;
+
+        let bn = match self.n_samples {
+            0 | 1 => 1.0,
+            2 => 1.196,
+            3 => 1.495,
+            4 => 1.363,
+            5 => 1.206,
+            6 => 1.200,
+            7 => 1.140,
+            8 => 1.129,
+            9 => 1.107,
+            _ => n / (n - 0.8),
+        };
+
+        MAD_ASYMPTOTIC_CONSTANT * bn
+    }
+
+    /// The finite-sample factors are obtained by Monte Carlo simulation of the normal samples,
+    /// see `simulate_shamos_factors` in the tests.
+    fn shamos_normalization_constant(&self) -> f64 {
         #[allow(clippy::cast_precision_loss)]
         let n = self.n_samples as f64;
 
-        let dn = if self.n_samples & 1 == 1 {
-            1.0 - 1.594 / n + 3.22 / n.powi(2)
-        } else {
-            1.0 - 3.672 / n + 11.087 / n.powi(2)
+        let dn = match self.n_samples {
+            0 | 1 => 1.0,
+            2 => 0.8453,
+            3 => 0.7697,
+            4 => 0.8635,
+            5 => 0.9079,
+            6 => 0.9087,
+            7 => 0.9369,
+            8 => 0.9421,
+            9 => 0.9484,
+            _ => 1.0 - 0.4116 / n - 0.4135 / n.powi(2),
         };
 
-        2.219_144_465_985_08 * dn
+        SHAMOS_ASYMPTOTIC_CONSTANT * dn
+    }
+}
+
+/// Constant, which makes the `p`-quantile of the pairwise absolute differences
+/// of the normal sample consistent, that is the reciprocal of the `p`-quantile of `|X_1 - X_2|`.
+fn generalized_qn_constant(p: f64) -> f64 {
+    1.0 / (SQRT_2 * normal_quantile((1.0 + p) / 2.0))
+}
+
+/// Standard normal distribution function.
+///
+/// This is the Taylor series of George Marsaglia, which is accurate to the absolute error
+/// of about `1e-15`.
+pub fn normal_cdf(x: f64) -> f64 {
+    // ln(sqrt(2π)):
+    const LN_SQRT_2PI: f64 = 0.918_938_533_204_672_7;
+
+    if x < -10.0 {
+        return 0.0;
+    }
+    if x > 10.0 {
+        return 1.0;
+    }
+
+    let (mut sum, mut term, mut i) = (x, x, 1.0);
+    loop {
+        i += 2.0;
+        term *= x * x / i;
+        let next_sum = sum + term;
+        #[allow(clippy::float_cmp)]
+        if next_sum == sum {
+            break;
+        }
+        sum = next_sum;
     }
+    sum.mul_add((-0.5f64).mul_add(x * x, -LN_SQRT_2PI).exp(), 0.5)
+}
+
+/// Inverse of the standard normal distribution function.
+///
+/// This is the rational approximation of Peter Acklam with relative error less than `1.15e-9`.
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 6] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+        1.0,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 5] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+        1.0,
+    ];
+    const P_LOW: f64 = 0.024_25;
+
+    let tail = |p: f64| {
+        let q = (-2.0 * p.ln()).sqrt();
+        polynomial(&C, q) / polynomial(&D, q)
+    };
+    if p < P_LOW {
+        tail(p)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        q * polynomial(&A, q * q) / polynomial(&B, q * q)
+    } else {
+        -tail(1.0 - p)
+    }
+}
+
+/// Evaluate the polynomial, whose coefficients start from the highest power.
+fn polynomial(coefficients: &[f64], x: f64) -> f64 {
+    coefficients
+        .iter()
+        .fold(0.0, |value, coefficient| value.mul_add(x, *coefficient))
 }
 
 impl<T: Into<Self>> From<ScaleEstimate<T>> for f64 {
     /// Calculate the actual scale estimate, that is the statistic multiplied by
     /// the normalization constant.
     fn from(estimate: ScaleEstimate<T>) -> Self {
-        estimate.normalization_constant() * estimate.statistic.into()
+        estimate.normalize(Normalization::FiniteSample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+    use crate::QnScaleEstimator;
+
+    /// Draw from the standard normal distribution.
+    fn normal(rng: &mut Rng) -> f64 {
+        loop {
+            let p = rng.f64();
+            if p != 0.0 {
+                return normal_quantile(p);
+            }
+        }
+    }
+
+    /// Fit `y = c · x` by the least squares, that is solve the normal equations
+    /// by the Gaussian elimination.
+    #[allow(clippy::needless_range_loop)]
+    fn fit<const K: usize>(rows: &[([f64; K], f64)]) -> [f64; K] {
+        let mut a = [[0.0; K]; K];
+        let mut b = [0.0; K];
+        for (x, y) in rows {
+            for i in 0..K {
+                for j in 0..K {
+                    a[i][j] = x[i].mul_add(x[j], a[i][j]);
+                }
+                b[i] = x[i].mul_add(*y, b[i]);
+            }
+        }
+        for i in 0..K {
+            for row in i + 1..K {
+                let factor = a[row][i] / a[i][i];
+                for column in i..K {
+                    a[row][column] = factor.mul_add(-a[i][column], a[row][column]);
+                }
+                b[row] = factor.mul_add(-b[i], b[row]);
+            }
+        }
+        let mut c = [0.0; K];
+        for i in (0..K).rev() {
+            let sum: f64 = (i + 1..K).map(|j| a[i][j] * c[j]).sum();
+            c[i] = (b[i] - sum) / a[i][i];
+        }
+        c
+    }
+
+    /// Regenerate [`QN_FACTORS`]:
+    ///
+    /// `cargo test --release simulate_qn_factors -- --ignored --nocapture`
+    ///
+    /// Each factor is `1 / (c E[Qn])` over `15,000,000 / n` normal samples of size `n`.
+    #[test]
+    #[ignore = "takes several minutes in release mode"]
+    #[allow(clippy::cast_precision_loss)]
+    fn simulate_qn_factors() {
+        let mut rng = Rng::with_seed(42);
+        for n in 2..=100_usize {
+            let n_replications = 15_000_000 / n;
+            let mut estimator = QnScaleEstimator::new(n);
+            let mut sum = 0.0;
+            for _ in 0..n_replications {
+                estimator.extend((0..n).map(|_| normal(&mut rng)));
+                sum += estimator.estimate().unwrap().statistic;
+            }
+            let factor = n_replications as f64 / (QN_ASYMPTOTIC_CONSTANT * sum);
+            println!("{n}: {factor:.4}");
+        }
+    }
+
+    /// Regenerate [`SN_FACTORS`] and the approximation for the larger samples:
+    ///
+    /// `cargo test --release simulate_sn_factors -- --ignored --nocapture`
+    ///
+    /// Each factor is `1 / (c E[Sn])` over `15,000,000 / n` normal samples of size `n`,
+    /// so the relative standard error is about `3e-4`. The approximation is fitted
+    /// to the factors for `n >= 50`, separately for the odd and even `n`.
+    #[test]
+    #[ignore = "takes several minutes in release mode"]
+    #[allow(clippy::cast_precision_loss)]
+    fn simulate_sn_factors() {
+        let mut rng = Rng::with_seed(42);
+        let mut factors = Vec::new();
+        for n in 2..=100_usize {
+            let n_replications = 15_000_000 / n;
+            let mut estimator = QnScaleEstimator::new(n);
+            let (mut sum, mut sum_of_squares) = (0.0, 0.0);
+            for _ in 0..n_replications {
+                estimator.extend((0..n).map(|_| normal(&mut rng)));
+                let statistic = estimator.sn().unwrap().statistic;
+                sum += statistic;
+                sum_of_squares = statistic.mul_add(statistic, sum_of_squares);
+            }
+            let mean = sum / n_replications as f64;
+            let standard_error = (sum_of_squares / n_replications as f64 - mean * mean).sqrt()
+                / (n_replications as f64).sqrt();
+            let factor = 1.0 / (SN_ASYMPTOTIC_CONSTANT * mean);
+            println!(
+                "{n}: {factor:.4} (relative standard error: {:.1e})",
+                standard_error / mean
+            );
+            factors.push((n, factor));
+        }
+        for (parity, name) in [(1, "odd"), (0, "even")] {
+            let tail: Vec<([f64; 2], f64)> = factors
+                .iter()
+                .filter(|(n, _)| *n >= 50 && n % 2 == parity)
+                .map(|(n, factor)| {
+                    let n = *n as f64;
+                    ([1.0 / n, 1.0 / (n * n)], factor - 1.0)
+                })
+                .collect();
+            let [a, b] = fit(&tail);
+            println!("{name}: 1 + {a:.4} / n + {b:.4} / n²");
+        }
+    }
+
+    /// Regenerate the Shamos factors and the approximation for the larger samples:
+    ///
+    /// `cargo test --release simulate_shamos_factors -- --ignored --nocapture`
+    ///
+    /// Each factor is `1 / (c E[Shamos])` over `15,000,000 / n` normal samples of size `n`.
+    /// The approximation is fitted to the factors for `n >= 10`.
+    #[test]
+    #[cfg(feature = "num-traits")]
+    #[ignore = "takes several minutes in release mode"]
+    #[allow(clippy::cast_precision_loss)]
+    fn simulate_shamos_factors() {
+        let mut rng = Rng::with_seed(42);
+        let mut factors = Vec::new();
+        for n in 2..=100_usize {
+            let n_replications = 15_000_000 / n;
+            let mut estimator = QnScaleEstimator::new(n);
+            let mut sum = 0.0;
+            for _ in 0..n_replications {
+                estimator.extend((0..n).map(|_| normal(&mut rng)));
+                sum += estimator.shamos().unwrap().statistic;
+            }
+            let factor = n_replications as f64 / (SHAMOS_ASYMPTOTIC_CONSTANT * sum);
+            println!("{n}: {factor:.4}");
+            factors.push((n, factor));
+        }
+        let tail: Vec<([f64; 2], f64)> = factors
+            .iter()
+            .filter(|(n, _)| *n >= 10)
+            .map(|(n, factor)| {
+                let n = *n as f64;
+                ([1.0 / n, 1.0 / (n * n)], factor - 1.0)
+            })
+            .collect();
+        let [a, b] = fit(&tail);
+        println!("1 + {a:.4} / n + {b:.4} / n²");
+    }
+
+    /// Regenerate [`GENERALIZED_QN_FACTORS`] and the approximation for the larger samples:
+    ///
+    /// `cargo test --release simulate_generalized_qn_factors -- --ignored --nocapture`
+    ///
+    /// Each factor is `1 / (c(p) E[Qn])` for every `h` except the original one. There are
+    /// `40,000,000 / n²` normal samples of size `n`, clamped to `20,000..=2,000,000`.
+    /// The approximation is fitted to the factors for `n >= 12`.
+    #[test]
+    #[ignore = "takes several minutes in release mode"]
+    #[allow(clippy::cast_precision_loss)]
+    fn simulate_generalized_qn_factors() {
+        let mut rng = Rng::with_seed(42);
+        let mut rows = Vec::new();
+        let (mut samples, mut differences) = (Vec::new(), Vec::new());
+        for n in 5..=100_usize {
+            let hs = n / 2 + 2..n;
+            let n_replications = (40_000_000 / (n * n)).clamp(20_000, 2_000_000);
+            let mut sums = vec![0.0; hs.len()];
+            for _ in 0..n_replications {
+                samples.clear();
+                samples.extend((0..n).map(|_| normal(&mut rng)));
+                differences.clear();
+                differences.extend(samples.iter().enumerate().flat_map(|(i, lhs)| {
+                    samples[i + 1..].iter().map(move |rhs| f64::abs(lhs - rhs))
+                }));
+                differences.sort_unstable_by(f64::total_cmp);
+                for (sum, h) in sums.iter_mut().zip(hs.clone()) {
+                    *sum += differences[h * (h - 1) / 2 - 1];
+                }
+            }
+            let mut factors = Vec::new();
+            for (sum, h) in sums.into_iter().zip(hs) {
+                let p = (h * (h - 1)) as f64 / (n * (n - 1)) as f64;
+                let factor = n_replications as f64 / (generalized_qn_constant(p) * sum);
+                factors.push(factor);
+                if n >= 12 {
+                    let (n, n_excluded) = (n as f64, (n - h) as f64);
+                    rows.push((
+                        [
+                            1.0 / n,
+                            p / n,
+                            p * p / n,
+                            1.0 / (n_excluded * n),
+                            1.0 / (n * n),
+                        ],
+                        factor - 1.0,
+                    ));
+                }
+            }
+            println!("{n}: {factors:.4?}");
+        }
+        let coefficients = fit(&rows);
+        println!(
+            "1 + ({:.4} + {:.4} p + {:.4} p²) / n + {:.4} / ((n - h) n) + {:.4} / n²",
+            coefficients[0], coefficients[1], coefficients[2], coefficients[3], coefficients[4],
+        );
+    }
+
+    #[test]
+    fn normal_quantile_ok() {
+        for (p, expected) in [
+            (0.001, -3.090_232_306),
+            (0.1, -1.281_551_566),
+            (0.5, 0.0),
+            (0.625, 0.318_639_364),
+            (0.975, 1.959_963_985),
+        ] {
+            let quantile = normal_quantile(p);
+            assert!(
+                (quantile - expected).abs() < 1e-8,
+                "p: {p}, quantile: {quantile}"
+            );
+        }
+    }
+
+    #[test]
+    fn normal_cdf_ok() {
+        for (x, expected) in [
+            (-3.0, 0.001_349_898_031_630_095),
+            (0.0, 0.5),
+            (1.0, 0.841_344_746_068_542_9),
+            (2.5, 0.993_790_334_674_224),
+        ] {
+            let p = normal_cdf(x);
+            assert!((p - expected).abs() < 1e-14, "x: {x}, p: {p}");
+        }
+    }
+
+    #[test]
+    fn generalized_qn_ok() {
+        // Asymptotically, it should be the original constant:
+        let estimate = ScaleEstimate {
+            n_samples: 1_000_000,
+            statistic: 1.0,
+            kind: ScaleKind::GeneralizedQn { h: 500_000 },
+            fallback: None,
+        };
+        let scale: f64 = estimate.into();
+        assert!((scale - 2.219_144).abs() < 1e-3, "scale: {scale}");
+    }
+
+    /// Nearby breakdown points should give nearby scales, even for the small samples.
+    #[test]
+    fn generalized_qn_small_sample_ok() {
+        let mut rng = Rng::with_seed(42);
+        for n in [7, 10] {
+            let mut estimators =
+                [0.5, 0.4, 0.3, 0.2].map(|bp| QnScaleEstimator::new(n).with_breakdown_point(bp));
+            let mut sums = [0.0; 4];
+            for _ in 0..20_000 {
+                let samples: Vec<f64> = (0..n).map(|_| normal(&mut rng)).collect();
+                for (estimator, sum) in estimators.iter_mut().zip(&mut sums) {
+                    estimator.extend(samples.iter().copied());
+                    *sum += f64::from(estimator.estimate().unwrap());
+                }
+            }
+            for sum in sums {
+                let ratio = sum / sums[0];
+                assert!((ratio - 1.0).abs() < 0.02, "n: {n}, ratio: {ratio}");
+            }
+        }
+    }
+
+    #[test]
+    fn small_sample_qn_ok() {
+        // The expected absolute difference of two standard normal samples is `2 / sqrt(π)`:
+        let estimate = ScaleEstimate {
+            n_samples: 2,
+            statistic: 1.0,
+            kind: ScaleKind::Qn,
+            fallback: None,
+        };
+        let scale: f64 = estimate.into();
+        assert!((scale - 0.886_227).abs() < 1e-3, "scale: {scale}");
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn normalize_ok() {
+        let estimate = ScaleEstimate {
+            n_samples: 10,
+            statistic: 2.0,
+            kind: ScaleKind::Qn,
+            fallback: None,
+        };
+        assert_eq!(estimate.normalize(Normalization::Raw), 2.0);
+        assert_eq!(
+            estimate.normalize(Normalization::Asymptotic),
+            2.0 * QN_ASYMPTOTIC_CONSTANT
+        );
+        assert!(estimate.normalize(Normalization::FiniteSample) < 2.0 * QN_ASYMPTOTIC_CONSTANT);
+
+        let estimate = ScaleEstimate {
+            fallback: Some(ImplosionFallback::Floor(5.0)),
+            ..estimate
+        };
+        assert_eq!(estimate.normalize(Normalization::Raw), 2.0);
+        assert_eq!(estimate.normalize(Normalization::Asymptotic), 5.0);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn implosion_fallback_ok() {
+        let estimate = ScaleEstimate {
+            n_samples: 10,
+            statistic: 0.0,
+            kind: ScaleKind::Qn,
+            fallback: Some(ImplosionFallback::Resolution(1.0)),
+        };
+        assert_eq!(estimate.normalize(Normalization::Raw), 0.0);
+        assert_eq!(f64::from(estimate), 1.0 / 12.0_f64.sqrt());
+        assert_eq!(estimate.applied_fallback(Normalization::Raw), None);
+        assert_eq!(
+            estimate.applied_fallback(Normalization::FiniteSample),
+            estimate.fallback
+        );
+
+        // The floor applies to the non-zero scales as well:
+        let estimate = ScaleEstimate {
+            statistic: 0.1,
+            fallback: Some(ImplosionFallback::Floor(0.5)),
+            ..estimate
+        };
+        assert_eq!(f64::from(estimate), 0.5);
+        assert_eq!(
+            estimate.applied_fallback(Normalization::FiniteSample),
+            estimate.fallback
+        );
+        let estimate = ScaleEstimate {
+            statistic: 1.0,
+            ..estimate
+        };
+        assert_eq!(estimate.applied_fallback(Normalization::FiniteSample), None);
+        assert_eq!(
+            f64::from(estimate),
+            f64::from(ScaleEstimate::new(10, 1.0, ScaleKind::Qn))
+        );
     }
 }
@@ -1,9 +1,13 @@
-use std::{fmt::Debug, ops::Sub};
+use std::{
+    cmp::Ordering,
+    fmt::Debug,
+    ops::{Add, Mul, Sub},
+};
 
 use crate::{
     dash_iter::DashIter,
-    pick_list::pick_list,
-    rank::{n_greater, n_smaller},
+    pick_list::{pick_list, weighted_pick_list},
+    rank::{n_greater, n_smaller, weighted_n_greater, weighted_n_smaller},
 };
 
 /// The heart of the estimator: select _k_-th order statistic of the virtual `X + (-X)` matrix.
@@ -22,30 +26,324 @@ where
     );
 
     // Starting with unit step, meaning the full window.
-    binary_select::<V, I>(window, k, k, 1, Vec::new()).0
+    binary_select(
+        window.clone(),
+        window,
+        |lhs, rhs| lhs - rhs,
+        k,
+        k,
+        1,
+        Vec::new(),
+    )
+    .0
+}
+
+/// Select the `k1`-th and `k2`-th order statistics of the virtual `X + (-X)` matrix at once.
+///
+/// **Note, that `k1` and `k2` start from `1`, and `k1` must not be less than `k2`.**
+#[cfg(feature = "num-traits")]
+#[must_use]
+pub fn select_kth_statistics<V, I>(window: I, k1: usize, k2: usize) -> (V, V)
+where
+    V: Copy + Debug + PartialOrd + Sub<V, Output = V>,
+    I: Clone + ExactSizeIterator<Item = V>,
+{
+    debug_assert!(
+        k2 <= k1 && (1..=window.len().pow(2)).contains(&k1) && k2 >= 1,
+        "window len: {}, k1: {k1}, k2: {k2}",
+        window.len(),
+    );
+
+    let (statistic_1, statistic_2, _) = binary_select(
+        window.clone(),
+        window,
+        |lhs, rhs| lhs - rhs,
+        k1,
+        k2,
+        1,
+        Vec::new(),
+    );
+    (statistic_1, statistic_2)
+}
+
+/// Select the `k1`-th and `k2`-th order statistics of the virtual `X + X` matrix,
+/// that is of the pairwise sums.
+///
+/// The columns are the reversed window, so that the matrix is sorted the same way
+/// as the `X + (-X)` one.
+///
+/// **Note, that `k1` and `k2` start from `1`, and `k1` must not be less than `k2`.**
+#[cfg(feature = "num-traits")]
+#[must_use]
+pub fn select_kth_sums<V, I>(window: I, k1: usize, k2: usize) -> (V, V)
+where
+    V: Copy + Debug + PartialOrd + Add<V, Output = V>,
+    I: Clone + DoubleEndedIterator<Item = V> + ExactSizeIterator,
+{
+    debug_assert!(
+        k2 <= k1 && (1..=window.len().pow(2)).contains(&k1) && k2 >= 1,
+        "window len: {}, k1: {k1}, k2: {k2}",
+        window.len(),
+    );
+
+    let (statistic_1, statistic_2, _) = binary_select(
+        window.clone(),
+        window.rev(),
+        |lhs, rhs| lhs + rhs,
+        k1,
+        k2,
+        1,
+        Vec::new(),
+    );
+    (statistic_1, statistic_2)
+}
+
+/// Select the `k`-th smallest of the pairwise sums `x_i + x_j` for `i <= j`,
+/// that is of the doubled Walsh averages.
+///
+/// The `X + X` matrix is symmetric: each sum `i < j` occurs twice in it, and each sum `i = j`
+/// occurs once. So, the statistic lies between the `(2k - n)`-th and `(2k - 1)`-th elements
+/// of the matrix, and at most `n` sums are in between.
+///
+/// **Note, that `k` starts from `1`.**
+#[cfg(feature = "num-traits")]
+#[must_use]
+pub fn select_kth_walsh_sum<V, I>(window: I, k: usize) -> V
+where
+    V: Copy + Debug + PartialOrd + Add<V, Output = V>,
+    I: Clone + DoubleEndedIterator<Item = V> + ExactSizeIterator,
+{
+    let n = window.len();
+    debug_assert!(
+        (1..=n * (n + 1) / 2).contains(&k),
+        "window len: {n}, k: {k}"
+    );
+
+    let diagonal = window.clone().map(|value| value + value);
+    if n == 1 {
+        return select_nth(&mut diagonal.collect::<Vec<_>>(), 0);
+    }
+
+    let (upper, lower) = select_kth_sums(
+        window.clone(),
+        (2 * k - 1).min(n * n),
+        (2 * k).saturating_sub(n).max(1),
+    );
+
+    // Number of the sums `i <= j`, which are not greater than the lower bound:
+    let n_not_greater = (n * n
+        - n_greater(
+            window.clone(),
+            window.clone().rev(),
+            |lhs, rhs| lhs + rhs,
+            lower,
+        )
+        + diagonal.clone().filter(|sum| *sum <= lower).count())
+        / 2;
+    if n_not_greater >= k {
+        return lower;
+    }
+
+    // Each sum strictly between the bounds occurs twice, once the diagonal is added:
+    let mut sums = Vec::with_capacity(2 * n);
+    pick_list(
+        window.clone(),
+        window.rev(),
+        |lhs, rhs| lhs + rhs,
+        lower,
+        upper,
+        &mut sums,
+    );
+    sums.extend(diagonal.filter(|sum| lower < *sum && *sum < upper));
+    let rank = k - n_not_greater;
+    if 2 * rank > sums.len() {
+        upper
+    } else {
+        select_nth(&mut sums, 2 * rank - 1)
+    }
+}
+
+/// Weighted counterpart of [`select_kth_statistic`]: select the smallest element of the virtual
+/// `X + (-X)` matrix such that the total weight of the elements not greater than it reaches `k`.
+///
+/// The matrix element `(i, j)` weighs `w_i * w_j`, so integer weights are equivalent to repeating
+/// the samples. `window` must be sorted by the values.
+///
+/// # Developer's notes
+///
+/// The `A-dash` trick of Mirzaian and Arjomandi does not carry the weights over, so this one
+/// follows the row-bounds pivoting of Johnson and Mizoguchi, which Croux and Rousseeuw used
+/// for the original `Qn`. Each row keeps a range of the candidate columns, and each iteration
+/// ranks the weighted median of the row medians, discarding at least a quarter of the candidates.
+#[must_use]
+pub fn select_weighted_kth_statistic<V, W>(window: &[(V, W)], k: W) -> V
+where
+    V: Copy + Debug + PartialOrd + Sub<V, Output = V>,
+    W: Copy
+        + Debug
+        + Default
+        + PartialOrd
+        + Add<W, Output = W>
+        + Sub<W, Output = W>
+        + Mul<W, Output = W>,
+{
+    let n = window.len();
+    let window_weight = window
+        .iter()
+        .fold(W::default(), |sum, (_, weight)| sum + *weight);
+    let total_weight = window_weight * window_weight;
+    debug_assert!(
+        n != 0 && W::default() < k && k <= total_weight,
+        "window len: {n}, k: {k:?}, total weight: {total_weight:?}"
+    );
+
+    // The candidates are `left[i]..right[i]` in each row `i`:
+    let mut left = vec![0; n];
+    let mut right = vec![n; n];
+    let mut boundaries = vec![0; n];
+    let mut row_medians = Vec::with_capacity(n);
+
+    // Total weight of the elements below all the candidates:
+    let mut weight_below = W::default();
+
+    loop {
+        let n_candidates: usize = left
+            .iter()
+            .zip(&right)
+            .map(|(left, right)| right - left)
+            .sum();
+        if n_candidates <= n {
+            break;
+        }
+
+        // Pick the median of the row medians, weighted by the number of candidates in the row:
+        row_medians.clear();
+        row_medians.extend(
+            window
+                .iter()
+                .zip(left.iter().zip(&right))
+                .filter(|(_, (left, right))| left < right)
+                .map(|((lhs, _), (left, right))| {
+                    (*lhs - window[(left + right) / 2].0, right - left)
+                }),
+        );
+        row_medians.sort_unstable_by(|(lhs, _), (rhs, _)| compare(lhs, rhs));
+        let mut n_counted = 0;
+        let (pivot, _) = *row_medians
+            .iter()
+            .find(|(_, n_row_candidates)| {
+                n_counted += n_row_candidates;
+                2 * n_counted >= n_candidates
+            })
+            .expect("the row medians should not be empty");
+
+        let weight_not_greater = total_weight - weighted_n_greater(window, pivot, &mut boundaries);
+        if weight_not_greater < k {
+            // The statistic is greater than the pivot, keep only the greater elements:
+            weight_below = weight_not_greater;
+            for (right, boundary) in right.iter_mut().zip(&boundaries) {
+                *right = (*right).min(*boundary);
+            }
+        } else if weighted_n_smaller(window, pivot, &mut boundaries) < k {
+            return pivot;
+        } else {
+            // The statistic is less than the pivot, keep only the smaller elements:
+            for (left, boundary) in left.iter_mut().zip(&boundaries) {
+                *left = (*left).max(*boundary);
+            }
+        }
+    }
+
+    let mut candidates = Vec::with_capacity(n);
+    weighted_pick_list(window, &left, &right, &mut candidates);
+    candidates.sort_unstable_by(|(lhs, _), (rhs, _)| compare(lhs, rhs));
+    let mut weight = weight_below;
+    for (value, candidate_weight) in &candidates {
+        weight = weight + *candidate_weight;
+        if weight >= k {
+            return *value;
+        }
+    }
+
+    // Rounding errors may prevent floating-point weights from reaching `k`:
+    candidates
+        .last()
+        .expect("there should be candidates left")
+        .0
+}
+
+/// Select _k_-th smallest element of the union of the two sorted sequences in `O(log n)`.
+///
+/// The sequences are accessed by index, which is handy for the distances from a center
+/// of a sorted sample: those on the left and those on the right are both sorted.
+///
+/// **Note, that `k` starts from `1`.**
+#[must_use]
+pub fn select_kth_of_two_sorted<V>(
+    left: impl Fn(usize) -> V,
+    n_left: usize,
+    right: impl Fn(usize) -> V,
+    n_right: usize,
+    k: usize,
+) -> V
+where
+    V: Copy + PartialOrd,
+{
+    debug_assert!(
+        (1..=n_left + n_right).contains(&k),
+        "n_left: {n_left}, n_right: {n_right}, k: {k}"
+    );
+
+    // Binary search over the number of the elements taken from the left:
+    let mut low = k.saturating_sub(n_right);
+    let mut high = k.min(n_left);
+    while low < high {
+        let n_taken = (low + high) / 2;
+        if left(n_taken) < right(k - n_taken - 1) {
+            low = n_taken + 1;
+        } else {
+            high = n_taken;
+        }
+    }
+
+    match (low, k - low) {
+        (0, n_taken_right) => right(n_taken_right - 1),
+        (n_taken_left, 0) => left(n_taken_left - 1),
+        (n_taken_left, n_taken_right) => {
+            let (left, right) = (left(n_taken_left - 1), right(n_taken_right - 1));
+            if left < right { right } else { left }
+        }
+    }
 }
 
 /// # Returns
 ///
-/// Tuple of the `k1`-th and `k2`-th elements of the matrix derived from `window` and negated `window`.
+/// Tuple of the `k1`-th and `k2`-th elements of the matrix derived from `rows` and `columns`,
+/// and the list buffer for reuse.
 ///
 /// P.S. Abandon hope all ye who enter here 💀
 #[must_use]
-fn binary_select<V, I>(
-    full_window: I,
+fn binary_select<V, I, J, C>(
+    full_rows: I,
+    full_columns: J,
+    combine: C,
     k1: usize,
     k2: usize,
     step: usize,
     list_buffer: Vec<V>,
 ) -> (V, V, Vec<V>)
 where
-    V: Copy + Debug + Default + PartialOrd + Sub<V, Output = V>,
+    V: Copy + Debug + PartialOrd,
     I: Clone + ExactSizeIterator<Item = V>,
+    J: Clone + ExactSizeIterator<Item = V>,
+    C: Copy + Fn(V, V) -> V,
 {
     // Current `A` matrix:
-    let window = DashIter::new(full_window.clone(), step);
-    let n = window.len();
+    let rows = DashIter::new(full_rows.clone(), step);
+    let columns = DashIter::new(full_columns.clone(), step);
+    let n = rows.len();
 
+    debug_assert_eq!(n, columns.len());
     debug_assert!(
         (n * n >= k1) && (k1 >= k2) && (k2 >= 1) && (k1 - k2 <= 4 * n - 4),
         "lemma 5.1 (Mirzaian & Arjomandi) should hold, but: n = {n}, k1 = {k1}, k2 = {k2}"
@@ -54,39 +352,45 @@ where
     debug_assert!(n >= 2);
     if n == 2 {
         return (
-            select_trivial(window.clone(), k1),
-            select_trivial(window, k2),
+            select_trivial(rows.clone(), columns.clone(), combine, k1),
+            select_trivial(rows, columns, combine, k2),
             list_buffer,
         );
     }
 
     // Define k1-dash and k2-dash from the papers:
     let k1_dash = if n & 1 == 0 {
-        // Original paper mentions `ceil(k1 / 4)`, which is simply `floor((k1 + 3) / 4)`
-        // (just consider all the possible remainders of 4).
-        n + 1 + (k1 + 3) / 4
+        // Original paper mentions `ceil(k1 / 4)`:
+        n + 1 + k1.div_ceil(4)
     } else {
         // `ceil(1 / 4 * (k1 + 2n + 1))` which is `floor(1 / 4 * (k1 + 2n + 1 + 3))` which is
         // `floor(1 / 4 * (k1 + 2n)) + 1`.
         (2 * n + k1) / 4 + 1
     };
 
-    // Surprisingly, here they used the same very trick they did NOT use for `k1`. Okay 🤔
-    let k2_dash = (k2 + 3) / 4;
+    // Papers mention `ceil(k2 / 4)` for both the odd and even `n`:
+    let k2_dash = k2.div_ceil(4);
 
     // Bi-select in the `A-dash` matrix and rank the candidates:
-    let (max_candidate, min_candidate, mut list_buffer) =
-        binary_select(full_window, k1_dash, k2_dash, step * 2, list_buffer);
+    let (max_candidate, min_candidate, mut list_buffer) = binary_select(
+        full_rows,
+        full_columns,
+        combine,
+        k1_dash,
+        k2_dash,
+        step * 2,
+        list_buffer,
+    );
     debug_assert!(min_candidate <= max_candidate, "`b <= a` should hold");
-    let rank_max = n_smaller(window.clone(), max_candidate); // ra-
-    let rank_min = n_greater(window.clone(), min_candidate); // rb+
+    let rank_max = n_smaller(rows.clone(), columns.clone(), combine, max_candidate); // ra-
+    let rank_min = n_greater(rows.clone(), columns.clone(), combine, min_candidate); // rb+
 
-    // We may not need the `L`, and we only need to build it once, so wrap the window into the flag.
-    let mut window = Some(window);
+    // We may not need the `L`, and we only need to build it once, so wrap the matrix into the flag.
+    let mut matrix = Some((rows, columns, combine));
 
     (
         select_statistic(
-            &mut window,
+            &mut matrix,
             (min_candidate, rank_min),
             (max_candidate, rank_max),
             k1,
@@ -94,7 +398,7 @@ where
             &mut list_buffer,
         ),
         select_statistic(
-            &mut window,
+            &mut matrix,
             (min_candidate, rank_min),
             (max_candidate, rank_max),
             k2,
@@ -107,8 +411,8 @@ where
 
 /// Convenience function to deduplicate the final selection in [`binary_select`].
 #[must_use]
-fn select_statistic<V, I>(
-    window: &mut Option<I>,
+fn select_statistic<V, I, J, C>(
+    matrix: &mut Option<(I, J, C)>,
     (min_candidate, rank_min): (V, usize),
     (max_candidate, rank_max): (V, usize),
     k: usize,
@@ -116,8 +420,10 @@ fn select_statistic<V, I>(
     list_buffer: &mut Vec<V>,
 ) -> V
 where
-    V: Copy + Debug + PartialOrd + Sub<V, Output = V>,
+    V: Copy + Debug + PartialOrd,
     I: Clone + ExactSizeIterator<Item = V>,
+    J: Clone + ExactSizeIterator<Item = V>,
+    C: Copy + Fn(V, V) -> V,
 {
     let n_elements = window_size * window_size;
 
@@ -127,29 +433,43 @@ where
     } else if k + rank_min <= n_elements {
         min_candidate
     } else {
-        if let Some(window) = window.take() {
-            pick_list(window, min_candidate, max_candidate, list_buffer);
+        if let Some((rows, columns, combine)) = matrix.take() {
+            pick_list(
+                rows,
+                columns,
+                combine,
+                min_candidate,
+                max_candidate,
+                list_buffer,
+            );
         }
         select_nth(list_buffer, k + rank_min - n_elements - 1)
     }
 }
 
-/// Handle the trivial case of a 2-window (the recursion basis for [`binary_select`]).
+/// Handle the trivial case of a 2×2 matrix (the recursion basis for [`binary_select`]).
 #[must_use]
-fn select_trivial<V, I>(mut window: I, k: usize) -> V
+fn select_trivial<V, I, J, C>(mut rows: I, mut columns: J, combine: C, k: usize) -> V
 where
-    I: Clone + ExactSizeIterator<Item = V>,
-    V: Copy + Default + Sub<V, Output = V>,
+    V: Copy + Debug + PartialOrd,
+    I: ExactSizeIterator<Item = V>,
+    J: ExactSizeIterator<Item = V>,
+    C: Fn(V, V) -> V,
 {
-    debug_assert_eq!(window.len(), 2);
-    let item_1 = window.next().unwrap();
-    let item_2 = window.next().unwrap();
-    match k {
-        1 => item_1 - item_2, // non-positive
-        2 | 3 => V::default(),
-        4 => item_2 - item_1, // non-negative
-        _ => panic!("`k` should be in `1..=4` but it is `{k}`"),
-    }
+    debug_assert_eq!(rows.len(), 2);
+    debug_assert!(
+        (1..=4).contains(&k),
+        "`k` should be in `1..=4` but it is `{k}`"
+    );
+    let (row_1, row_2) = (rows.next().unwrap(), rows.next().unwrap());
+    let (column_1, column_2) = (columns.next().unwrap(), columns.next().unwrap());
+    let mut elements = [
+        combine(row_1, column_1),
+        combine(row_1, column_2),
+        combine(row_2, column_1),
+        combine(row_2, column_2),
+    ];
+    select_nth(&mut elements, k - 1)
 }
 
 /// Select the Nth largest element from the `L` ([`PartialOrd`] adapter).
@@ -158,16 +478,22 @@ where
 ///
 /// The two elements in `L` cannot be ordered.
 #[must_use]
-fn select_nth<V>(l: &mut [V], index: usize) -> V
+pub fn select_nth<V>(l: &mut [V], index: usize) -> V
 where
     V: Copy + Debug + PartialOrd,
 {
     debug_assert!(index < l.len(), "out of range: l = {l:?}, index = {index}");
-    *l.select_nth_unstable_by(index, |lhs, rhs| {
-        lhs.partial_cmp(rhs)
-            .unwrap_or_else(|| panic!("`{lhs:?}` and `{rhs:?}` cannot be ordered"))
-    })
-    .1
+    *l.select_nth_unstable_by(index, compare).1
+}
+
+/// [`PartialOrd`] adapter for sorting and selection.
+///
+/// # Panics
+///
+/// The two elements cannot be ordered.
+fn compare<V: Debug + PartialOrd>(lhs: &V, rhs: &V) -> Ordering {
+    lhs.partial_cmp(rhs)
+        .unwrap_or_else(|| panic!("`{lhs:?}` and `{rhs:?}` cannot be ordered"))
 }
 
 #[cfg(test)]
@@ -219,9 +545,10 @@ mod tests {
         let statistics: Vec<_> = (1..=16)
             .map(|k| select_kth_statistic(window.clone(), k))
             .collect();
-        assert_eq!(statistics, [
-            -3, -2, -2, -1, -1, -1, 0, 0, 0, 0, 1, 1, 1, 2, 2, 3
-        ]);
+        assert_eq!(
+            statistics,
+            [-3, -2, -2, -1, -1, -1, 0, 0, 0, 0, 1, 1, 1, 2, 2, 3]
+        );
     }
 
     /// # Matrix
@@ -239,8 +566,99 @@ mod tests {
         let statistics: Vec<_> = (1..=25)
             .map(|k| select_kth_statistic(window.clone(), k))
             .collect();
-        assert_eq!(statistics, [
-            -4, -3, -3, -2, -2, -2, -1, -1, -1, -1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 3, 3, 4
-        ]);
+        assert_eq!(
+            statistics,
+            [
+                -4, -3, -3, -2, -2, -2, -1, -1, -1, -1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 3, 3, 4
+            ]
+        );
+    }
+
+    /// # Matrix
+    ///
+    /// ```text
+    /// 4, 3, 2
+    /// 5, 4, 3
+    /// 6, 5, 4
+    /// ```
+    #[test]
+    #[cfg(feature = "num-traits")]
+    fn sums_3x3_ok() {
+        let window = [1, 2, 3].into_iter();
+        let statistics: Vec<_> = (1..=9)
+            .map(|k| select_kth_sums(window.clone(), k, k).0)
+            .collect();
+        assert_eq!(statistics, [2, 3, 3, 4, 4, 4, 5, 5, 6]);
+        assert_eq!(select_kth_sums(window, 5, 4), (4, 4));
+    }
+
+    /// Compare against the naïve selection, including the ties.
+    #[test]
+    #[cfg(feature = "num-traits")]
+    fn walsh_sums_random_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let mut window: Vec<i32> = (0..rng.usize(1..30)).map(|_| rng.i32(0..20)).collect();
+            window.sort_unstable();
+
+            let mut sums: Vec<i32> = window
+                .iter()
+                .enumerate()
+                .flat_map(|(i, lhs)| window[i..].iter().map(move |rhs| lhs + rhs))
+                .collect();
+            sums.sort_unstable();
+
+            for (index, expected) in sums.into_iter().enumerate() {
+                assert_eq!(
+                    select_kth_walsh_sum(window.iter().copied(), index + 1),
+                    expected,
+                    "window: {window:?}, k: {}",
+                    index + 1
+                );
+            }
+        }
+    }
+
+    /// Compare against the naïve selection on the expanded samples.
+    #[test]
+    fn weighted_random_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        for _ in 0..100 {
+            let mut window: Vec<(i32, u64)> = (0..rng.usize(1..20))
+                .map(|_| (rng.i32(-10..10), rng.u64(1..5)))
+                .collect();
+            window.sort_unstable();
+
+            let mut expanded: Vec<i32> = window
+                .iter()
+                .flat_map(|(lhs, lhs_weight)| {
+                    window.iter().flat_map(move |(rhs, rhs_weight)| {
+                        let weight = usize::try_from(lhs_weight * rhs_weight).unwrap();
+                        std::iter::repeat_n(lhs - rhs, weight)
+                    })
+                })
+                .collect();
+            expanded.sort_unstable();
+
+            for (index, expected) in expanded.into_iter().enumerate() {
+                let k = index as u64 + 1;
+                assert_eq!(
+                    select_weighted_kth_statistic(&window, k),
+                    expected,
+                    "window: {window:?}, k: {k}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_fractional_ok() {
+        // Each difference weighs `0.5 * 0.5 = 0.25`: `1 - 2 = -1`, `1 - 1 = 0`, `2 - 2 = 0`,
+        // and `2 - 1 = 1`. So the cumulative weight is 0.25 at -1, 0.75 at 0, and 1.0 at 1,
+        // and the smallest element reaching `k` is selected:
+        let window = [(1, 0.5), (2, 0.5)];
+        assert_eq!(select_weighted_kth_statistic(&window, 0.1), -1);
+        assert_eq!(select_weighted_kth_statistic(&window, 0.5), 0);
+        assert_eq!(select_weighted_kth_statistic(&window, 0.9), 1);
     }
 }
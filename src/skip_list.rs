@@ -0,0 +1,366 @@
+//! Indexable skip list, which provides `O(log n)` insertion, removal, and indexing.
+//!
+//! # Developer's notes
+//!
+//! - The nodes live in an arena and are linked by indices, vacant nodes get reused.
+//!   This allows the iterator to be a cheap cloneable cursor, which is crucial
+//!   for the selection algorithm.
+//!
+//! - Every link remembers its width, that is the number of the bottom-level steps it spans.
+//!   Links pointing to nowhere span till the virtual element past the end of the list.
+//!
+//! - The bottom level is doubly linked to allow iterating from the back.
+
+use std::iter::FusedIterator;
+
+use crate::sorted_vec::SortedStorage;
+
+/// The link is pointing to nowhere.
+const NIL: usize = usize::MAX;
+
+/// Enough for `4^32` elements.
+const MAX_LEVEL: usize = 32;
+
+#[derive(Copy, Clone)]
+struct Link {
+    next: usize,
+    width: usize,
+}
+
+struct Node<T> {
+    value: T,
+    prev: usize,
+    links: Vec<Link>,
+}
+
+/// Sorted storage based on an indexable skip list.
+///
+/// Unlike [`crate::SortedVec`], insertion and removal are `O(log n)`, which makes pushing cheap
+/// for large windows. The price is the `O(log n)` indexing and slower iteration.
+#[must_use]
+pub struct SkipList<T> {
+    head: Vec<Link>,
+    tail: usize,
+    nodes: Vec<Node<T>>,
+    vacant: Vec<usize>,
+    len: usize,
+    random_state: u64,
+}
+
+impl<T> SkipList<T> {
+    /// Links of the specified node, [`None`] stands for the head.
+    fn links(&self, node: Option<usize>) -> &[Link] {
+        node.map_or(&self.head, |index| &self.nodes[index].links)
+    }
+
+    fn links_mut(&mut self, node: Option<usize>) -> &mut [Link] {
+        match node {
+            Some(index) => &mut self.nodes[index].links,
+            None => &mut self.head,
+        }
+    }
+
+    /// Draw a level from the geometric distribution with `p = 1/4` (xorshift64).
+    fn random_level(&mut self) -> usize {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        (1 + self.random_state.trailing_zeros() as usize / 2).min(MAX_LEVEL)
+    }
+
+    fn allocate(&mut self, value: T, prev: usize, level: usize) -> usize {
+        if let Some(index) = self.vacant.pop() {
+            let node = &mut self.nodes[index];
+            node.value = value;
+            node.prev = prev;
+            node.links.clear();
+            index
+        } else {
+            self.nodes.push(Node {
+                value,
+                prev,
+                links: Vec::with_capacity(level),
+            });
+            self.nodes.len() - 1
+        }
+    }
+}
+
+impl<T: Copy + PartialOrd> SkipList<T> {
+    /// Find the last node with the value strictly less than the specified one on each level.
+    ///
+    /// # Returns
+    ///
+    /// The nodes along with their ranks (the head's rank is `0`).
+    fn find_predecessors(&self, value: &T) -> [(Option<usize>, usize); MAX_LEVEL] {
+        let mut predecessors = [(None, 0); MAX_LEVEL];
+        let mut node = None;
+        let mut rank = 0;
+        for level in (0..self.head.len()).rev() {
+            loop {
+                let link = self.links(node)[level];
+                if link.next != NIL && self.nodes[link.next].value < *value {
+                    rank += link.width;
+                    node = Some(link.next);
+                } else {
+                    break;
+                }
+            }
+            predecessors[level] = (node, rank);
+        }
+        predecessors
+    }
+}
+
+impl<T: Copy + PartialOrd> SortedStorage<T> for SkipList<T> {
+    type Iter<'a>
+        = Iter<'a, T>
+    where
+        T: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            head: Vec::new(),
+            tail: NIL,
+            nodes: Vec::with_capacity(capacity),
+            vacant: Vec::new(),
+            len: 0,
+            random_state: 0x2545_F491_4F6C_DD1D,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let mut node = None;
+        let mut remaining = index + 1;
+        for level in (0..self.head.len()).rev() {
+            loop {
+                let link = self.links(node)[level];
+                if link.next != NIL && link.width <= remaining {
+                    remaining -= link.width;
+                    node = Some(link.next);
+                } else {
+                    break;
+                }
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        node.map(|index| self.nodes[index].value)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        Iter {
+            list: self,
+            front: self.head.first().map_or(NIL, |link| link.next),
+            back: self.tail,
+            remaining: self.len,
+        }
+    }
+
+    fn insert_sorted(&mut self, value: T) {
+        let n_levels = self.random_level();
+        while self.head.len() < n_levels {
+            self.head.push(Link {
+                next: NIL,
+                width: self.len + 1,
+            });
+        }
+
+        let predecessors = self.find_predecessors(&value);
+        let (prev, prev_rank) = predecessors[0];
+        let rank = prev_rank + 1;
+        let index = self.allocate(value, prev.unwrap_or(NIL), n_levels);
+
+        for (level, (node, node_rank)) in predecessors.into_iter().enumerate().take(n_levels) {
+            let link = self.links(node)[level];
+            self.nodes[index].links.push(Link {
+                next: link.next,
+                width: node_rank + link.width + 1 - rank,
+            });
+            self.links_mut(node)[level] = Link {
+                next: index,
+                width: rank - node_rank,
+            };
+        }
+        for (level, (node, _)) in predecessors
+            .into_iter()
+            .enumerate()
+            .take(self.head.len())
+            .skip(n_levels)
+        {
+            self.links_mut(node)[level].width += 1;
+        }
+
+        match self.nodes[index].links[0].next {
+            NIL => self.tail = index,
+            next => self.nodes[next].prev = index,
+        }
+        self.len += 1;
+    }
+
+    fn remove_value(&mut self, value: &T) -> Option<T> {
+        let predecessors = self.find_predecessors(value);
+        let index = self.links(predecessors[0].0).first()?.next;
+        if index == NIL || self.nodes[index].value != *value {
+            return None;
+        }
+
+        let n_levels = self.nodes[index].links.len();
+        for (level, (node, _)) in predecessors.into_iter().enumerate().take(self.head.len()) {
+            if level < n_levels {
+                let removed_link = self.nodes[index].links[level];
+                let link = &mut self.links_mut(node)[level];
+                link.next = removed_link.next;
+                link.width += removed_link.width - 1;
+            } else {
+                self.links_mut(node)[level].width -= 1;
+            }
+        }
+
+        let prev = self.nodes[index].prev;
+        match self.nodes[index].links[0].next {
+            NIL => self.tail = prev,
+            next => self.nodes[next].prev = prev,
+        }
+        self.vacant.push(index);
+        self.len -= 1;
+        Some(self.nodes[index].value)
+    }
+
+    fn clear(&mut self) {
+        self.head.clear();
+        self.tail = NIL;
+        self.nodes.clear();
+        self.vacant.clear();
+        self.len = 0;
+    }
+
+    /// This is a linear-time operation, since each node owns its links.
+    fn heap_size(&self) -> usize {
+        let n_links = self.head.capacity()
+            + self
+                .nodes
+                .iter()
+                .map(|node| node.links.capacity())
+                .sum::<usize>();
+        self.nodes.capacity() * size_of::<Node<T>>()
+            + self.vacant.capacity() * size_of::<usize>()
+            + n_links * size_of::<Link>()
+    }
+}
+
+/// Iterator over the [`SkipList`] values in ascending order.
+#[must_use]
+pub struct Iter<'a, T> {
+    list: &'a SkipList<T>,
+    front: usize,
+    back: usize,
+    remaining: usize,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            list: self.list,
+            front: self.front,
+            back: self.back,
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<T: Copy> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &self.list.nodes[self.front];
+        self.front = node.links[0].next;
+        self.remaining -= 1;
+        Some(node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T: Copy> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = &self.list.nodes[self.back];
+        self.back = node.prev;
+        self.remaining -= 1;
+        Some(node.value)
+    }
+}
+
+impl<T: Copy> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T: Copy> FusedIterator for Iter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+    use crate::sorted_vec::SortedVec;
+
+    #[test]
+    fn empty_ok() {
+        let list = SkipList::<i32>::with_capacity(0);
+        assert_eq!(list.get(0), None);
+        assert_eq!(list.iter().next(), None);
+        assert_eq!(list.median(), None);
+    }
+
+    #[test]
+    fn remove_missing_ok() {
+        let mut list = SkipList::with_capacity(0);
+        list.insert_sorted(1);
+        list.insert_sorted(3);
+        assert_eq!(list.remove_value(&2), None);
+        assert_eq!(list.remove_value(&4), None);
+        assert_eq!(list.remove_value(&3), Some(3));
+        assert_eq!(list.iter().collect::<Vec<_>>(), [1]);
+    }
+
+    /// Compare against the plain sorted vector.
+    #[test]
+    fn random_operations_ok() {
+        let mut rng = Rng::with_seed(42);
+        let mut list = SkipList::with_capacity(0);
+        let mut expected = SortedVec(Vec::new());
+
+        for _ in 0..5000 {
+            let value = rng.i32(0..100);
+            if rng.bool() {
+                list.insert_sorted(value);
+                expected.insert_sorted(value);
+            } else {
+                assert_eq!(list.remove_value(&value), expected.remove_value(&value));
+            }
+
+            assert_eq!(list.len(), expected.len());
+            assert!(list.iter().eq(expected.iter()));
+            assert!(list.iter().rev().eq(expected.iter().rev()));
+            assert_eq!(list.median(), expected.median());
+        }
+
+        for index in 0..=expected.len() {
+            assert_eq!(list.get(index), expected.get(index));
+        }
+    }
+}
@@ -1,39 +1,132 @@
+use std::{iter::Copied, slice};
+
 use crate::RawMedian;
 
-#[must_use]
-pub struct SortedVec<T>(pub Vec<T>);
+/// Storage which keeps the window samples sorted.
+///
+/// The iterators are consumed by the selection algorithm, which clones them a lot,
+/// so the cloning must be cheap.
+pub trait SortedStorage<T> {
+    /// Iterator over the samples in ascending order.
+    type Iter<'a>: Clone + DoubleEndedIterator<Item = T> + ExactSizeIterator
+    where
+        Self: 'a;
+
+    /// Create an empty storage, which is able to hold the specified number of samples
+    /// without reallocating, if that makes sense for the storage.
+    fn with_capacity(capacity: usize) -> Self;
+
+    /// Number of the stored samples.
+    fn len(&self) -> usize;
+
+    /// Whether the storage holds no samples.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the `index`-th smallest sample (starting from `0`).
+    fn get(&self, index: usize) -> Option<T>;
+
+    /// Iterate over the samples in ascending order.
+    fn iter(&self) -> Self::Iter<'_>;
 
-impl<T: Copy> SortedVec<T> {
-    /// Get the vector median.
-    pub fn median(&self) -> Option<RawMedian<T>> {
-        if self.0.is_empty() {
+    /// Insert the value keeping the storage sorted.
+    fn insert_sorted(&mut self, value: T);
+
+    /// Remove a value, which is equal to the specified one.
+    ///
+    /// # Returns
+    ///
+    /// The removed value, or [`None`] if there was no such value.
+    fn remove_value(&mut self, value: &T) -> Option<T>;
+
+    /// Remove all the samples.
+    fn clear(&mut self);
+
+    /// Number of bytes allocated on the heap.
+    fn heap_size(&self) -> usize;
+
+    /// Index of the first sample, for which the predicate is false, assuming that
+    /// the predicate is true for all the preceding samples.
+    fn partition_point(&self, mut predicate: impl FnMut(&T) -> bool) -> usize {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let index = (low + high) / 2;
+            match self.get(index) {
+                Some(value) if predicate(&value) => low = index + 1,
+                _ => high = index,
+            }
+        }
+        low
+    }
+
+    /// Get the sample median.
+    fn median(&self) -> Option<RawMedian<T>> {
+        let len = self.len();
+        if len == 0 {
             None
-        } else if self.0.len() & 1 == 1 {
-            Some(RawMedian::Odd(self.0[self.0.len() / 2]))
+        } else if len & 1 == 1 {
+            self.get(len / 2).map(RawMedian::Odd)
         } else {
-            let i = self.0.len() / 2;
-            Some(RawMedian::Even(self.0[i - 1], self.0[i]))
+            Some(RawMedian::Even(self.get(len / 2 - 1)?, self.get(len / 2)?))
         }
     }
 }
 
-impl<T: PartialOrd> SortedVec<T> {
-    pub fn insert_sorted(&mut self, value: T) {
+/// Plain sorted vector: constant-time indexing, but linear-time insertion and removal.
+#[must_use]
+pub struct SortedVec<T>(pub(crate) Vec<T>);
+
+impl<T: Copy + PartialOrd> SortedStorage<T> for SortedVec<T> {
+    type Iter<'a>
+        = Copied<slice::Iter<'a, T>>
+    where
+        T: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self(Vec::with_capacity(capacity))
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        self.0.get(index).copied()
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.0.iter().copied()
+    }
+
+    fn partition_point(&self, predicate: impl FnMut(&T) -> bool) -> usize {
+        self.0.partition_point(predicate)
+    }
+
+    fn insert_sorted(&mut self, value: T) {
         let index = self.0.partition_point(|x| x < &value);
         self.0.insert(index, value);
     }
 
-    pub fn remove_value(&mut self, value: &T) -> Option<T> {
+    fn remove_value(&mut self, value: &T) -> Option<T> {
         self.0
             .iter()
             .rposition(|existing_value| existing_value == value)
             .map(|index| self.0.remove(index))
     }
+
+    fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn heap_size(&self) -> usize {
+        self.0.capacity() * size_of::<T>()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{RawMedian, sorted_vec::SortedVec};
+    use super::*;
 
     #[test]
     fn empty_ok() {
@@ -0,0 +1,137 @@
+use std::{fmt::Debug, ops::Sub};
+
+use crate::{ImplosionFallback, QnScaleEstimator, outlier::robust_score};
+
+/// Extension trait, which standardizes a stream by the rolling median and `Qn`.
+pub trait RobustStandardize: Iterator + Sized {
+    /// Standardize each element by the median and normalized `Qn` of the preceding
+    /// `window_len` elements, that is `(x - median) / scale`.
+    ///
+    /// Each element is standardized *before* it enters the window. While the window is still
+    /// filling, [`None`] is yielded, see [`RobustStandardized::with_min_samples`].
+    /// When the scale is zero, the result is zero for the element equal to the median,
+    /// and infinite for the other elements.
+    ///
+    /// # Panics
+    ///
+    /// Zero `window_len`.
+    fn robust_standardize(self, window_len: usize) -> RobustStandardized<Self, Self::Item> {
+        RobustStandardized {
+            iter: self,
+            estimator: QnScaleEstimator::new(window_len),
+            min_samples: window_len,
+        }
+    }
+}
+
+impl<I: Iterator> RobustStandardize for I {}
+
+/// Iterator adapter, which is returned by [`RobustStandardize::robust_standardize`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct RobustStandardized<I, T> {
+    iter: I,
+    estimator: QnScaleEstimator<T>,
+    min_samples: usize,
+}
+
+impl<I, T> RobustStandardized<I, T> {
+    /// Start standardizing as soon as the window contains `min_samples` elements,
+    /// instead of waiting for the full window.
+    ///
+    /// # Panics
+    ///
+    /// Zero `min_samples`.
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        assert_ne!(
+            min_samples, 0,
+            "the minimum number of samples must be positive"
+        );
+        self.min_samples = min_samples;
+        self
+    }
+
+    /// Avoid the zero scale on heavily tied data,
+    /// see [`QnScaleEstimator::with_implosion_fallback`].
+    pub fn with_implosion_fallback(mut self, fallback: ImplosionFallback<T>) -> Self
+    where
+        T: Copy + PartialOrd,
+    {
+        self.estimator = self.estimator.with_implosion_fallback(fallback);
+        self
+    }
+}
+
+impl<I, T> Iterator for RobustStandardized<I, T>
+where
+    I: Iterator<Item = T>,
+    T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T> + Into<f64>,
+{
+    type Item = Option<f64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.iter.next()?;
+        let score = if self.estimator.n_samples() >= self.min_samples {
+            self.estimator
+                .seed()
+                .map(|(median, scale)| robust_score(value.into(), median, scale))
+        } else {
+            None
+        };
+        self.estimator.push(value);
+        Some(score)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warm_up_ok() {
+        let scores: Vec<_> = [1, 2, 3, 4].into_iter().robust_standardize(3).collect();
+        assert_eq!(scores[..3], [None, None, None]);
+
+        let mut estimator = QnScaleEstimator::new(3);
+        estimator.extend([1, 2, 3]);
+        let scale: f64 = estimator.estimate().unwrap().into();
+        assert!((scores[3].unwrap() - 2.0 / scale).abs() < 1e-9);
+    }
+
+    #[test]
+    fn min_samples_ok() {
+        let scores: Vec<_> = [1, 2, 3, 4]
+            .into_iter()
+            .robust_standardize(3)
+            .with_min_samples(1)
+            .map(|score| score.map(f64::is_finite))
+            .collect();
+        assert_eq!(scores, [None, Some(false), Some(true), Some(true)]);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn zero_scale_ok() {
+        let scores: Vec<_> = [5, 5, 5, 5, 6]
+            .into_iter()
+            .robust_standardize(2)
+            .flatten()
+            .collect();
+        assert_eq!(scores, [0.0, 0.0, f64::INFINITY]);
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn implosion_fallback_ok() {
+        let scores: Vec<_> = [5.0, 5.0, 5.0, 5.0, 6.0]
+            .into_iter()
+            .robust_standardize(2)
+            .with_implosion_fallback(ImplosionFallback::Floor(0.5))
+            .flatten()
+            .collect();
+        assert_eq!(scores, [0.0, 0.0, 2.0]);
+    }
+}
@@ -0,0 +1,426 @@
+//! Sorted storage based on a treap, which is augmented with the subtree moments.
+//!
+//! # Developer's notes
+//!
+//! - The nodes live in an arena and are linked by indices, vacant nodes get reused,
+//!   just like in the skip list.
+//!
+//! - Every node keeps the size, mean, and sum of the squared deviations of its subtree.
+//!   They are merged from the children on each change with the pairwise formula of Chan et al.
+//!
+//! - A range query merges the moments of the `O(log n)` subtrees, which lie within the range,
+//!   rather than subtracts the prefix moments.
+//!
+//! - The iterator is a pair of ranks, and each step is an `O(log n)` lookup.
+
+use std::{cmp::Ordering, iter::FusedIterator, ops::Range};
+
+use crate::sorted_vec::SortedStorage;
+
+/// The link is pointing to nowhere.
+const NIL: usize = usize::MAX;
+
+struct Node<T> {
+    value: T,
+    priority: u64,
+    left: usize,
+    right: usize,
+
+    /// Moments of the values in the subtree.
+    moments: RangeMoments,
+}
+
+/// Number of samples, mean, and sum of the squared deviations from the mean
+/// of a range of the sorted samples.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RangeMoments {
+    /// Number of the samples.
+    pub len: usize,
+
+    /// Mean of the samples, or zero when there are none.
+    pub mean: f64,
+
+    /// Sum of the squared deviations of the samples from their mean, also known as `M2`.
+    ///
+    /// Divided by `len - 1`, it gives the sample variance.
+    pub sum_of_squared_deviations: f64,
+}
+
+impl RangeMoments {
+    /// Moments of the `len` samples, which are all equal to the `value`.
+    #[must_use]
+    pub const fn repeat(value: f64, len: usize) -> Self {
+        Self {
+            len,
+            mean: value,
+            sum_of_squared_deviations: 0.0,
+        }
+    }
+
+    /// Moments of the union of the two disjoint sets of samples.
+    #[must_use]
+    pub fn merge(self, other: Self) -> Self {
+        if other.len == 0 {
+            return self;
+        }
+        if self.len == 0 {
+            return other;
+        }
+        let len = self.len + other.len;
+        let delta = other.mean - self.mean;
+        #[allow(clippy::cast_precision_loss)]
+        let (self_len, other_weight) = (self.len as f64, other.len as f64 / len as f64);
+        Self {
+            len,
+            mean: delta.mul_add(other_weight, self.mean),
+            sum_of_squared_deviations: (delta * delta * self_len).mul_add(
+                other_weight,
+                self.sum_of_squared_deviations + other.sum_of_squared_deviations,
+            ),
+        }
+    }
+}
+
+/// Sorted storage, which maintains the count, mean, and sum of the squared deviations
+/// of each subtree of the sorted samples.
+///
+/// Insertion, removal, and indexing are `O(log n)`, just like in [`crate::SkipList`].
+/// Additionally, the mean and variance of any range of the sorted samples are `O(log n)`,
+/// which enables the trimmed and winsorized means,
+/// see [`crate::QnScaleEstimator::trimmed_mean`].
+#[must_use]
+pub struct SumTree<T> {
+    root: usize,
+    nodes: Vec<Node<T>>,
+    vacant: Vec<usize>,
+    random_state: u64,
+}
+
+impl<T> SumTree<T> {
+    fn len_of(&self, node: usize) -> usize {
+        if node == NIL {
+            0
+        } else {
+            self.nodes[node].moments.len
+        }
+    }
+
+    /// Draw a node priority (xorshift64).
+    fn random_priority(&mut self) -> u64 {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        self.random_state
+    }
+
+    fn allocate(&mut self, value: T) -> usize {
+        let priority = self.random_priority();
+        let node = Node {
+            value,
+            priority,
+            left: NIL,
+            right: NIL,
+            moments: RangeMoments::default(),
+        };
+        if let Some(index) = self.vacant.pop() {
+            self.nodes[index] = node;
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    /// Node of the `index`-th smallest value.
+    fn find(&self, mut index: usize) -> Option<usize> {
+        let mut node = self.root;
+        while node != NIL {
+            let left_len = self.len_of(self.nodes[node].left);
+            match index.cmp(&left_len) {
+                Ordering::Less => node = self.nodes[node].left,
+                Ordering::Equal => return Some(node),
+                Ordering::Greater => {
+                    index -= left_len + 1;
+                    node = self.nodes[node].right;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<T: Copy + Into<f64>> SumTree<T> {
+    /// Merge the trees, assuming that all the values in the `left` one do not exceed
+    /// the values in the `right` one.
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+        if self.nodes[left].priority > self.nodes[right].priority {
+            self.nodes[left].right = self.merge(self.nodes[left].right, right);
+            self.update(left);
+            left
+        } else {
+            self.nodes[right].left = self.merge(left, self.nodes[right].left);
+            self.update(right);
+            right
+        }
+    }
+
+    /// Split the tree into the values, for which the predicate is true, and the rest,
+    /// assuming that the predicate is true for all the preceding values.
+    fn split(&mut self, node: usize, predicate: &impl Fn(&T) -> bool) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+        if predicate(&self.nodes[node].value) {
+            let (left, right) = self.split(self.nodes[node].right, predicate);
+            self.nodes[node].right = left;
+            self.update(node);
+            (node, right)
+        } else {
+            let (left, right) = self.split(self.nodes[node].left, predicate);
+            self.nodes[node].left = right;
+            self.update(node);
+            (left, node)
+        }
+    }
+
+    /// Recalculate the node moments from its children.
+    fn update(&mut self, node: usize) {
+        let Node {
+            value, left, right, ..
+        } = self.nodes[node];
+        let moments = self
+            .moments_of(left)
+            .merge(RangeMoments::repeat(value.into(), 1))
+            .merge(self.moments_of(right));
+        self.nodes[node].moments = moments;
+    }
+
+    fn moments_of(&self, node: usize) -> RangeMoments {
+        if node == NIL {
+            RangeMoments::default()
+        } else {
+            self.nodes[node].moments
+        }
+    }
+
+    /// Moments of the subtree values within the range of ranks in the subtree.
+    fn moments_within(&self, node: usize, range: Range<usize>) -> RangeMoments {
+        if node == NIL || range.is_empty() {
+            return RangeMoments::default();
+        }
+        let Node {
+            value,
+            left,
+            right,
+            moments,
+            ..
+        } = self.nodes[node];
+        if range.start == 0 && range.end >= moments.len {
+            return moments;
+        }
+        let left_len = self.len_of(left);
+        let mut moments = self.moments_within(left, range.start..range.end.min(left_len));
+        if range.contains(&left_len) {
+            moments = moments.merge(RangeMoments::repeat(value.into(), 1));
+        }
+        let right_range =
+            range.start.saturating_sub(left_len + 1)..range.end.saturating_sub(left_len + 1);
+        moments.merge(self.moments_within(right, right_range))
+    }
+
+    /// Moments of the sorted samples within the range of ranks (starting from `0`).
+    ///
+    /// This is an `O(log n)` operation.
+    ///
+    /// # Panics
+    ///
+    /// The range is out of bounds.
+    #[must_use]
+    pub fn range_moments(&self, range: Range<usize>) -> RangeMoments {
+        assert!(
+            range.start <= range.end && range.end <= self.len_of(self.root),
+            "the range should be within the storage"
+        );
+        self.moments_within(self.root, range)
+    }
+}
+
+impl<T: Copy + PartialOrd + Into<f64>> SortedStorage<T> for SumTree<T> {
+    type Iter<'a>
+        = Iter<'a, T>
+    where
+        T: 'a;
+
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            root: NIL,
+            nodes: Vec::with_capacity(capacity),
+            vacant: Vec::new(),
+            random_state: 0x2545_F491_4F6C_DD1D,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len_of(self.root)
+    }
+
+    fn get(&self, index: usize) -> Option<T> {
+        self.find(index).map(|node| self.nodes[node].value)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        Iter {
+            tree: self,
+            front: 0,
+            back: self.len(),
+        }
+    }
+
+    fn insert_sorted(&mut self, value: T) {
+        let (left, right) = self.split(self.root, &|other| *other < value);
+        let node = self.allocate(value);
+        self.update(node);
+        let left = self.merge(left, node);
+        self.root = self.merge(left, right);
+    }
+
+    fn remove_value(&mut self, value: &T) -> Option<T> {
+        let (left, right) = self.split(self.root, &|other| other < value);
+        let (middle, right) = self.split(right, &|other| other <= value);
+        let (middle, removed_value) = if middle == NIL {
+            (NIL, None)
+        } else {
+            let Node {
+                value, left, right, ..
+            } = self.nodes[middle];
+            self.vacant.push(middle);
+            (self.merge(left, right), Some(value))
+        };
+        let left = self.merge(left, middle);
+        self.root = self.merge(left, right);
+        removed_value
+    }
+
+    fn clear(&mut self) {
+        self.root = NIL;
+        self.nodes.clear();
+        self.vacant.clear();
+    }
+
+    fn heap_size(&self) -> usize {
+        self.nodes.capacity() * size_of::<Node<T>>() + self.vacant.capacity() * size_of::<usize>()
+    }
+}
+
+/// Iterator over the [`SumTree`] values in ascending order.
+#[must_use]
+pub struct Iter<'a, T> {
+    tree: &'a SumTree<T>,
+    front: usize,
+    back: usize,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree,
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+impl<T: Copy> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let node = self.tree.find(self.front)?;
+        self.front += 1;
+        Some(self.tree.nodes[node].value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T: Copy> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        let node = self.tree.find(self.back)?;
+        Some(self.tree.nodes[node].value)
+    }
+}
+
+impl<T: Copy> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T: Copy> FusedIterator for Iter<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use fastrand::Rng;
+
+    use super::*;
+    use crate::sorted_vec::SortedVec;
+
+    #[test]
+    fn empty_ok() {
+        let tree = SumTree::<f64>::with_capacity(0);
+        assert_eq!(tree.get(0), None);
+        assert_eq!(tree.iter().next(), None);
+        assert_eq!(tree.range_moments(0..0), RangeMoments::default());
+    }
+
+    /// Compare against the plain sorted vector.
+    #[test]
+    fn random_operations_ok() {
+        let mut rng = Rng::with_seed(42);
+        let mut tree = SumTree::with_capacity(0);
+        let mut expected = SortedVec(Vec::new());
+
+        for _ in 0..5000 {
+            let value = rng.i32(0..100);
+            if rng.bool() {
+                tree.insert_sorted(value);
+                expected.insert_sorted(value);
+            } else {
+                assert_eq!(tree.remove_value(&value), expected.remove_value(&value));
+            }
+
+            assert_eq!(tree.len(), expected.len());
+            assert!(tree.iter().eq(expected.iter()));
+            assert!(tree.iter().rev().eq(expected.iter().rev()));
+
+            let start = rng.usize(0..=expected.len());
+            let end = rng.usize(start..=expected.len());
+            let moments = tree.range_moments(start..end);
+            let range = &expected.0[start..end];
+            assert_eq!(moments.len, range.len());
+            if !range.is_empty() {
+                #[allow(clippy::cast_precision_loss)]
+                let mean = f64::from(range.iter().sum::<i32>()) / range.len() as f64;
+                let sum_of_squared_deviations: f64 = range
+                    .iter()
+                    .map(|value| (f64::from(*value) - mean).powi(2))
+                    .sum();
+                assert!((moments.mean - mean).abs() < 1e-9);
+                assert!(
+                    (moments.sum_of_squared_deviations - sum_of_squared_deviations).abs() < 1e-6
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::{fmt::Debug, ops::Sub};
+
+use crate::{
+    ChronologicalStorage,
+    RawMedian,
+    scale::{ScaleEstimate, ScaleKind},
+    select::select_weighted_kth_statistic,
+    window::Window,
+};
+
+/// `Qn` estimator of scale for pre-aggregated samples, such as histogram buckets.
+///
+/// Each push carries a value along with its count, and the estimate is exactly the same
+/// as if each value were pushed `count` times. The sorted storage keeps the multiplicities
+/// instead of the repeated values, and the selection counts the weighted pairs.
+#[must_use = "constructing an estimator without using it makes no sense"]
+pub struct WeightedQnScaleEstimator<T> {
+    /// Pushed values along with their counts.
+    chronological: Window<(T, usize)>,
+
+    /// Distinct values along with their total counts in the window.
+    sorted: Vec<(T, usize)>,
+
+    /// Total count in the window.
+    n_samples: usize,
+}
+
+impl<T> WeightedQnScaleEstimator<T> {
+    /// Create a new estimator with the specified window length.
+    ///
+    /// The window length limits the number of pushes, regardless of their counts.
+    ///
+    /// # Panics
+    ///
+    /// Zero `window_len`.
+    pub fn new(window_len: usize) -> Self {
+        Self {
+            chronological: Window::new(window_len),
+            sorted: Vec::with_capacity(window_len),
+            n_samples: 0,
+        }
+    }
+
+    /// Clear the current sample window.
+    pub fn clear(&mut self) {
+        self.chronological.clear();
+        self.sorted.clear();
+        self.n_samples = 0;
+    }
+
+    /// Total count of the samples in the window.
+    #[must_use]
+    pub const fn n_samples(&self) -> usize {
+        self.n_samples
+    }
+}
+
+impl<T: Copy + PartialOrd> WeightedQnScaleEstimator<T> {
+    /// Push the upcoming value, which has been seen `count` times.
+    ///
+    /// If the current window is already at its maximum length, the oldest push
+    /// gets discarded along with its count. Zero count is ignored.
+    ///
+    /// This operation is `O(n_distinct)`, where `n_distinct` is the number of distinct values
+    /// in the window.
+    ///
+    /// # Panics
+    ///
+    /// The total count, including the push about to be discarded, overflows [`usize`].
+    /// The estimator is left unchanged in that case.
+    pub fn push_weighted(&mut self, value: T, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let n_samples = self
+            .n_samples
+            .checked_add(count)
+            .expect("the total count should not overflow");
+
+        if let Some((popped_value, popped_count)) = self.chronological.push((value, count)) {
+            let index = self.position(&popped_value);
+            let entry = self
+                .sorted
+                .get_mut(index)
+                .filter(|(value, _)| *value == popped_value)
+                .expect("the popped value should also be present in the sorted vector");
+            entry.1 -= popped_count;
+            if entry.1 == 0 {
+                self.sorted.remove(index);
+            }
+            self.n_samples = n_samples - popped_count;
+        } else {
+            self.n_samples = n_samples;
+        }
+
+        let index = self.position(&value);
+        match self.sorted.get_mut(index) {
+            Some((existing_value, existing_count)) if *existing_value == value => {
+                *existing_count += count;
+            }
+            _ => self.sorted.insert(index, (value, count)),
+        }
+    }
+
+    /// Push the upcoming value, which has been seen once.
+    pub fn push(&mut self, value: T) {
+        self.push_weighted(value, 1);
+    }
+
+    /// Push multiple values along with their counts.
+    pub fn extend_weighted(&mut self, iter: impl IntoIterator<Item = (T, usize)>) {
+        for (value, count) in iter {
+            self.push_weighted(value, count);
+        }
+    }
+
+    /// Position of the value in the sorted vector, or where it would be inserted.
+    fn position(&self, value: &T) -> usize {
+        self.sorted.partition_point(|(other, _)| other < value)
+    }
+
+    /// Get the `index`-th smallest sample of the expanded window (starting from `0`).
+    fn get(&self, mut index: usize) -> Option<T> {
+        for (value, count) in &self.sorted {
+            if index < *count {
+                return Some(*value);
+            }
+            index -= count;
+        }
+        None
+    }
+
+    /// Obtain the sample median.
+    ///
+    /// This is an `O(n_distinct)` operation.
+    ///
+    /// # Returns
+    ///
+    /// The sample median, or [`None`] – if the sample is empty.
+    #[must_use]
+    pub fn median(&self) -> Option<RawMedian<T>> {
+        let n = self.n_samples;
+        if n == 0 {
+            None
+        } else if n & 1 == 1 {
+            self.get(n / 2).map(RawMedian::Odd)
+        } else {
+            Some(RawMedian::Even(self.get(n / 2 - 1)?, self.get(n / 2)?))
+        }
+    }
+}
+
+impl<T: Copy + Debug + Default + PartialOrd + Sub<T, Output = T>> WeightedQnScaleEstimator<T> {
+    /// Calculate the estimate of scale.
+    ///
+    /// This is an `O(n_distinct log^2 n_distinct)` operation.
+    ///
+    /// # Returns
+    ///
+    /// Qn estimate of scale of the expanded sample, or [`None`] if the window contains no samples.
+    #[must_use = "calculating the scale without using it makes no sense"]
+    pub fn estimate(&self) -> Option<ScaleEstimate<T>> {
+        let n = self.n_samples;
+        if n == 0 {
+            return None;
+        }
+
+        let statistic = if n == 1 {
+            T::default()
+        } else {
+            // The total weight of the pairs is `n²`, which overflows `usize` for the large counts:
+            let sorted: Vec<(T, u128)> = self
+                .sorted
+                .iter()
+                .map(|(value, count)| (*value, *count as u128))
+                .collect();
+            let n = n as u128;
+            let h = n / 2 + 1;
+            let k = h * (h - 1) / 2 + n + n * (n - 1) / 2;
+            select_weighted_kth_statistic(&sorted, k)
+        };
+
+        Some(ScaleEstimate::new(n, statistic, ScaleKind::Qn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QnScaleEstimator;
+
+    #[test]
+    fn empty_ok() {
+        let mut estimator = WeightedQnScaleEstimator::<i32>::new(1);
+        estimator.push_weighted(42, 0);
+        assert_eq!(estimator.estimate(), None);
+        assert_eq!(estimator.median(), None);
+    }
+
+    #[test]
+    fn window_overflow_ok() {
+        let mut estimator = WeightedQnScaleEstimator::new(2);
+        estimator.extend_weighted([(1, 100), (2, 3), (1, 2)]);
+        assert_eq!(estimator.n_samples(), 5);
+        assert_eq!(estimator.median(), Some(RawMedian::Odd(2)));
+    }
+
+    /// The number of the weighted pairs exceeds `usize`.
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn large_counts_ok() {
+        let mut estimator = WeightedQnScaleEstimator::new(4);
+        estimator.extend_weighted([0, 10, 30, 60].map(|value| (value, 1 << 32)));
+        let scale = estimator.estimate().unwrap();
+        assert_eq!(scale.n_samples, 1 << 34);
+        assert_eq!(scale.statistic, 10);
+    }
+
+    #[test]
+    #[should_panic = "the total count should not overflow"]
+    fn total_count_overflow_panics() {
+        let mut estimator = WeightedQnScaleEstimator::new(2);
+        estimator.push_weighted(1, usize::MAX);
+        estimator.push(2);
+    }
+
+    /// Compare against pushing the repeated values.
+    #[test]
+    fn expanded_ok() {
+        let mut rng = fastrand::Rng::with_seed(42);
+        let mut estimator = WeightedQnScaleEstimator::new(20);
+        let mut expected_estimator = QnScaleEstimator::landmark();
+        let mut pushes = Vec::new();
+
+        for _ in 0..200 {
+            let value = rng.i32(0..50);
+            let count = rng.usize(1..10);
+            estimator.push_weighted(value, count);
+            pushes.push((value, count));
+            if pushes.len() > 20 {
+                let (popped_value, popped_count) = pushes.remove(0);
+                for _ in 0..popped_count {
+                    expected_estimator.retract(&popped_value).unwrap();
+                }
+            }
+            for _ in 0..count {
+                expected_estimator.push(value);
+            }
+
+            assert_eq!(estimator.estimate(), expected_estimator.estimate());
+            assert_eq!(estimator.median(), expected_estimator.median());
+        }
+    }
+}
@@ -1,16 +1,366 @@
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    ops::{Add, Sub},
+};
 
+/// Storage which keeps the samples in their chronological order and decides which of them
+/// should be evicted.
+pub trait ChronologicalStorage<T> {
+    /// Number of samples currently in the window.
+    fn len(&self) -> usize;
+
+    /// Whether the window contains no samples.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop all the samples.
+    fn clear(&mut self);
+
+    /// Remove the most recent sample, which is equal to the specified one.
+    ///
+    /// # Returns
+    ///
+    /// Whether the sample has been removed.
+    fn remove_value(&mut self, value: &T) -> bool
+    where
+        T: PartialEq;
+
+    /// Number of bytes allocated on the heap.
+    fn heap_size(&self) -> usize;
+}
+
+/// Count-based window, which holds the specified number of the most recent samples.
 #[must_use]
-pub struct Window<T>(pub VecDeque<T>);
+pub struct Window<T> {
+    items: VecDeque<T>,
+    max_len: usize,
+}
 
 impl<T> Window<T> {
-    pub fn push(&mut self, item: T) -> Option<T> {
-        let popped_item = if self.0.len() == self.0.capacity() {
-            self.0.pop_front()
+    /// # Panics
+    ///
+    /// Zero `max_len`.
+    pub(crate) fn new(max_len: usize) -> Self {
+        assert_ne!(max_len, 0, "the window length must be positive");
+        Self {
+            items: VecDeque::with_capacity(max_len),
+            max_len,
+        }
+    }
+
+    pub(crate) const fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// # Panics
+    ///
+    /// Zero `max_len`.
+    pub(crate) fn set_max_len(&mut self, max_len: usize) {
+        assert_ne!(max_len, 0, "the window length must be positive");
+        self.max_len = max_len;
+    }
+
+    pub(crate) fn push(&mut self, item: T) -> Option<T> {
+        let popped_item = if self.items.len() >= self.max_len {
+            self.items.pop_front()
         } else {
             None
         };
-        self.0.push_back(item);
+        self.items.push_back(item);
         popped_item
     }
+
+    /// Pop the oldest sample, if the window is longer than its maximum length.
+    pub(crate) fn pop_excess(&mut self) -> Option<T> {
+        if self.items.len() > self.max_len {
+            self.items.pop_front()
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> ChronologicalStorage<T> for Window<T> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn remove_value(&mut self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.items
+            .iter()
+            .rposition(|item| item == value)
+            .and_then(|index| self.items.remove(index))
+            .is_some()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.items.capacity() * size_of::<T>()
+    }
+}
+
+/// Landmark window, which never evicts anything and holds all the samples
+/// since the last landmark.
+///
+/// Since nothing gets evicted, there is no need to store the samples chronologically,
+/// so only the sorted storage grows.
+#[must_use]
+pub struct Landmark {
+    len: usize,
+    epoch: u64,
+}
+
+impl Landmark {
+    pub(crate) const fn new() -> Self {
+        Self { len: 0, epoch: 0 }
+    }
+
+    pub(crate) fn push(&mut self) {
+        self.len += 1;
+    }
+
+    pub(crate) const fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Drop all the samples and start a new epoch.
+    pub(crate) fn reset(&mut self) {
+        self.len = 0;
+        self.epoch += 1;
+    }
+}
+
+impl<T> ChronologicalStorage<T> for Landmark {
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// The samples are not stored, so only the length gets updated.
+    fn remove_value(&mut self, _value: &T) -> bool {
+        if self.len == 0 {
+            false
+        } else {
+            self.len -= 1;
+            true
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+/// Time-based window, which holds the samples not older than the specified horizon.
+///
+/// The window follows event time rather than arrival order: samples are kept ordered
+/// by their timestamps, and eviction is driven by the watermark, which trails
+/// the maximum seen timestamp by the allowed lateness.
+///
+/// `Ts` is a timestamp type, for example, [`std::time::Instant`] or UNIX time in milliseconds.
+/// `D` is the type of difference between two timestamps, for example, [`std::time::Duration`].
+#[must_use]
+pub struct TimedWindow<Ts, D, T> {
+    items: VecDeque<(Ts, T)>,
+    horizon: D,
+    allowed_lateness: D,
+    max_timestamp: Option<Ts>,
+    n_late_samples: usize,
+}
+
+impl<Ts, D, T> TimedWindow<Ts, D, T> {
+    pub(crate) const fn new(horizon: D, allowed_lateness: D) -> Self {
+        Self {
+            items: VecDeque::new(),
+            horizon,
+            allowed_lateness,
+            max_timestamp: None,
+            n_late_samples: 0,
+        }
+    }
+
+    pub(crate) fn set_allowed_lateness(&mut self, allowed_lateness: D) {
+        self.allowed_lateness = allowed_lateness;
+    }
+
+    /// Number of the samples rejected because they arrived later than the allowed lateness.
+    pub(crate) const fn n_late_samples(&self) -> usize {
+        self.n_late_samples
+    }
+}
+
+impl<Ts, D, T> TimedWindow<Ts, D, T>
+where
+    Ts: Copy + PartialOrd + Sub<Ts, Output = D>,
+    D: Copy + PartialOrd + Add<D, Output = D>,
+{
+    /// Insert the sample at its chronological position.
+    ///
+    /// # Returns
+    ///
+    /// [`LateSample`], if the sample is behind the watermark.
+    pub(crate) fn push(&mut self, timestamp: Ts, value: T) -> Result<(), LateSample<Ts, T>> {
+        match self.max_timestamp {
+            Some(max_timestamp) if timestamp < max_timestamp => {
+                if max_timestamp - timestamp > self.allowed_lateness {
+                    self.n_late_samples += 1;
+                    return Err(LateSample { timestamp, value });
+                }
+                // Late samples are usually close to the back, so the insertion is cheap:
+                let index = self.items.partition_point(|(other, _)| *other <= timestamp);
+                self.items.insert(index, (timestamp, value));
+            }
+            _ => {
+                self.max_timestamp = Some(timestamp);
+                self.items.push_back((timestamp, value));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pop the oldest sample, if it is older than the horizon relatively to the watermark.
+    pub(crate) fn pop_expired(&mut self) -> Option<T> {
+        let max_timestamp = self.max_timestamp?;
+        let (oldest_timestamp, _) = self.items.front()?;
+        if max_timestamp - *oldest_timestamp > self.allowed_lateness + self.horizon {
+            self.items.pop_front().map(|(_, value)| value)
+        } else {
+            None
+        }
+    }
+}
+
+impl<Ts, D, T> ChronologicalStorage<T> for TimedWindow<Ts, D, T> {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Drop all the samples and reset the watermark.
+    fn clear(&mut self) {
+        self.items.clear();
+        self.max_timestamp = None;
+    }
+
+    /// The watermark stays intact.
+    fn remove_value(&mut self, value: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.items
+            .iter()
+            .rposition(|(_, item)| item == value)
+            .and_then(|index| self.items.remove(index))
+            .is_some()
+    }
+
+    fn heap_size(&self) -> usize {
+        self.items.capacity() * size_of::<(Ts, T)>()
+    }
+}
+
+/// Sample rejected by [`TimedWindow`] because its timestamp is behind the watermark.
+#[must_use]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LateSample<Ts, T> {
+    pub timestamp: Ts,
+    pub value: T,
+}
+
+impl<Ts, T> Display for LateSample<Ts, T> {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter.write_str("the sample is behind the watermark")
+    }
+}
+
+impl<Ts: Debug, T: Debug> Error for LateSample<Ts, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_ok() {
+        let mut window = Window::new(2);
+        assert_eq!(window.push(1), None);
+        assert_eq!(window.push(2), None);
+        assert_eq!(window.push(3), Some(1));
+
+        window.set_max_len(1);
+        assert_eq!(window.pop_excess(), Some(2));
+        assert_eq!(window.pop_excess(), None);
+
+        window.set_max_len(3);
+        assert_eq!(window.push(4), None);
+        assert_eq!(window.push(5), None);
+        assert_eq!(window.push(6), Some(3));
+    }
+
+    #[test]
+    #[should_panic = "the window length must be positive"]
+    fn zero_window_len_panics() {
+        let _ = Window::<i32>::new(0);
+    }
+
+    #[test]
+    fn timed_window_ok() {
+        let mut window = TimedWindow::new(10_u64, 0);
+
+        window.push(0_u64, 1).unwrap();
+        window.push(5, 2).unwrap();
+        assert_eq!(window.pop_expired(), None);
+
+        window.push(10, 3).unwrap();
+        assert_eq!(
+            window.pop_expired(),
+            None,
+            "the horizon itself should be inclusive"
+        );
+
+        window.push(16, 4).unwrap();
+        assert_eq!(window.pop_expired(), Some(1));
+        assert_eq!(window.pop_expired(), Some(2));
+        assert_eq!(window.pop_expired(), None);
+        assert_eq!(window.len(), 2);
+    }
+
+    #[test]
+    fn out_of_order_ok() {
+        let mut window = TimedWindow::new(10_u64, 3);
+
+        window.push(10_u64, 1).unwrap();
+        window.push(7, 2).unwrap();
+        window.push(9, 3).unwrap();
+        assert_eq!(
+            window.push(6, 4),
+            Err(LateSample {
+                timestamp: 6,
+                value: 4
+            })
+        );
+        assert_eq!(window.n_late_samples(), 1);
+        assert_eq!(
+            window.items,
+            [(7, 2), (9, 3), (10, 1)],
+            "the samples should be ordered by event time"
+        );
+
+        // Watermark is now at 17, so everything older than 7 gets evicted:
+        window.push(20, 5).unwrap();
+        assert_eq!(window.pop_expired(), None);
+        window.push(21, 6).unwrap();
+        assert_eq!(window.pop_expired(), Some(2));
+        assert_eq!(window.pop_expired(), None);
+    }
 }